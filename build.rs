@@ -1,7 +1,19 @@
+use std::{env, process::Command};
+
 fn main() {
     glib_build_tools::compile_resources(
-        &["resources"],
+        &["resources", "data"],
         "resources/resources.gresource.xml",
         "compiled.gresource",
     );
+
+    // compile the GSettings schema next to the build output,
+    // so the app can find it when run from the build tree without installing it first
+    println!("cargo:rerun-if-changed=data");
+    let out_dir = env::var("OUT_DIR").unwrap();
+    let status = Command::new("glib-compile-schemas")
+        .args(["--strict", "--targetdir", &out_dir, "data"])
+        .status()
+        .expect("glib-compile-schemas needs to be installed.");
+    assert!(status.success(), "glib-compile-schemas failed to compile the schema.");
 }
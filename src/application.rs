@@ -1,47 +1,493 @@
-use std::borrow::Borrow;
+use std::{
+    cell::{Cell, RefCell},
+    rc::Rc,
+};
 
-use gtk::glib::{self, Bytes};
+use async_channel::Sender;
+use gettextrs::{gettext, ngettext};
+use gtk::gio;
+use gtk::glib::{self, clone, Bytes, VariantTy};
 use adw::{
     prelude::*, Application
 };
 use reqwest::Client;
+use tokio::task::AbortHandle;
 
 use crate::{
-    feed_page::FeedPage, story_object::StoryData, story_page::StoryPage, transform::spawn_cards_fetch_and_send, window::GliderCloneWindow
+    comment_object::CommentData,
+    data_saver::{is_data_saver_active, page_size, watch_data_saver},
+    data_usage::save_data_usage,
+    deep_link::DeepLink, favicon::set_image_client, feed_page::FeedPage,
+    prefetch::CommentPrefetcher,
+    http_cache::{http_cache, set_cache_size},
+    i18n::fill,
+    network::{set_max_concurrent_requests, set_offline, set_request_timeout, Feed, FeedSource},
+    offline_page::OfflinePage, offline_store::SavedStory,
+    session::{Session, SessionStory},
+    settings::settings, story_object::StoryData, story_page::StoryPage,
+    transform::{
+        spawn_cards_fetch_and_send, spawn_comments_fetch_and_send, spawn_item_resolve_and_send,
+        spawn_offline_save_and_send, spawn_user_fetch_and_send,
+    },
+    user_page::{UserData, UserPage}, window::GliderCloneWindow
 };
 
+// the reverse-DNS id the desktop file, metainfo, icons and settings schema are all named after
+pub const APP_ID: &str = "io.github.martin_niemann.GtkGliderClone";
+
+// how often the relative times on cards and comments are brought up to date, in seconds
+const TIME_REFRESH_INTERVAL: u32 = 60;
+
 pub enum Event {
-    SentStoryData(Vec<StoryData>),
-    ClickedStory(),
+    // a new batch of stories is on its way, with the number of stories in it.
+    // the stories events all start with the number of the batch they belong to
+    StartedStories(u32, usize),
+    // one story of the batch, sent in rank order
+    SentStory(u32, StoryData),
+    // the batch is complete, with the number of requests for it that failed
+    FinishedStories(u32, usize),
+    // not a single story of the batch could be loaded, with the number of requests that failed
+    FailedStories(u32, usize),
+    ClickedStory(u32),
+    SentComments(StoryData, Vec<CommentData>),
+    // the text of the article of a story saved for offline reading, sent after its comments
+    SentArticle(u32, String),
+    // an item opened by id, which may be a comment deep inside a thread
+    OpenItem(u32),
+    // the story an opened item belongs to, and the comment to show if the item was one
+    ResolvedItem(u32, Option<u32>),
+    OpenUser(String),
+    SentUser(UserData),
+    OpenSource(FeedSource),
+    ShowOffline,
+    // how many stories of a batch being saved for offline reading are done, out of how many
+    OfflineProgress(usize, usize),
+    SavedOffline(SavedStory),
+    // the batch is done, with the number of stories saved and the number that failed
+    FinishedOffline(usize, usize),
 }
 
 pub struct App {}
 
+// a kind of fetch of which only the latest one matters, like the stories of a feed or the comments of a story,
+// so starting a new one cancels the one before it
+#[derive(Default)]
+struct CurrentFetch {
+    generation: Cell<u32>,
+    abort_handle: RefCell<Option<AbortHandle>>,
+    // whether the current fetch runs in the background, to be merged into what is shown rather than replace it
+    background: Cell<bool>,
+}
+
+impl CurrentFetch {
+    // cancels the fetch running so far, and returns the generation of the one taking its place
+    fn supersede(&self) -> u32 {
+        if let Some(abort_handle) = self.abort_handle.take() {
+            abort_handle.abort();
+        }
+        self.background.set(false);
+        self.generation.set(self.generation.get().wrapping_add(1));
+        self.generation.get()
+    }
+
+    fn set_abort_handle(&self, abort_handle: AbortHandle) {
+        self.abort_handle.replace(Some(abort_handle));
+    }
+
+    fn is_current(&self, generation: u32) -> bool {
+        self.generation.get() == generation
+    }
+
+    fn is_background(&self) -> bool {
+        self.background.get()
+    }
+}
+
 impl App {
     pub fn new() -> Application {
         let application = Application::builder()
-            .application_id("com.example.FirstAdwaitaApp")
+            .application_id(APP_ID)
+            .flags(gio::ApplicationFlags::HANDLES_OPEN | gio::ApplicationFlags::HANDLES_COMMAND_LINE)
             .build();
 
+        application.add_main_option(
+            "feed",
+            glib::Char::from(b'f'),
+            glib::OptionFlags::NONE,
+            glib::OptionArg::String,
+            &gettext("Show a feed: top, new, best, ask, show or job"),
+            Some(gettext("FEED").as_str()),
+        );
+        application.add_main_option(
+            "item",
+            glib::Char::from(b'i'),
+            glib::OptionFlags::NONE,
+            glib::OptionArg::Int,
+            &gettext("Open a story or comment by its id"),
+            Some(gettext("ID").as_str()),
+        );
+        application.add_main_option(
+            "search",
+            glib::Char::from(b's'),
+            glib::OptionFlags::NONE,
+            glib::OptionArg::String,
+            &gettext("Search for stories"),
+            Some(gettext("QUERY").as_str()),
+        );
+        application.add_main_option(
+            "offline",
+            glib::Char::from(0),
+            glib::OptionFlags::NONE,
+            glib::OptionArg::None,
+            &gettext("Only show what has been stored, without going online"),
+            None,
+        );
+
+        // the options are parsed by whichever instance was started, then handled by the one already running, if any,
+        // so a second launch opens its feed, item or search in the existing window
+        application.connect_command_line(|app, command_line| {
+            let options = command_line.options_dict();
+            let mut exit_status = 0;
+
+            // going offline is for the whole session, so it is only up to the launch that starts the app.
+            // a second launch asking for it would otherwise cut off the running app for good
+            let went_offline = options.contains("offline") && !command_line.is_remote();
+            if went_offline {
+                set_offline(true);
+            } else if options.contains("offline") {
+                command_line.printerr_literal("--offline only applies when the app isn't running yet\n");
+                exit_status = 1;
+            }
+
+            app.activate();
+
+            if let Ok(Some(feed)) = options.lookup::<String>("feed") {
+                app.activate_action("show-feed", Some(&feed.to_variant()));
+            }
+            if let Ok(Some(id)) = options.lookup::<i32>("item") {
+                // item ids start at 1
+                match u32::try_from(id).ok().filter(|id| *id > 0) {
+                    Some(id) => app.activate_action("open-item", Some(&id.to_variant())),
+                    None => {
+                        command_line.printerr_literal(&format!("Not a valid item id: {}\n", id));
+                        exit_status = 1;
+                    }
+                }
+            }
+            if let Ok(Some(query)) = options.lookup::<String>("search") {
+                app.activate_action("search", Some(&query.to_variant()));
+            }
+
+            // anything else on the command line is a link, handled like links opened from the desktop.
+            // the ones that aren't Hacker News links are reported back to the launch they came from
+            let mut files: Vec<gio::File> = vec![];
+            for argument in command_line.arguments().iter().skip(1) {
+                let file = command_line.create_file_for_arg(argument);
+                if DeepLink::parse(&file.uri()).is_some() {
+                    files.push(file);
+                } else {
+                    command_line.printerr_literal(&format!("Cannot open {}\n", file.uri()));
+                    exit_status = 1;
+                }
+            }
+            if !files.is_empty() {
+                app.open(&files, "");
+            }
+
+            if went_offline {
+                if let Some(window) = app.active_window().and_downcast::<GliderCloneWindow>() {
+                    window.add_toast(adw::Toast::new(&gettext("Offline, only showing stored stories")));
+                }
+            }
+
+            exit_status
+        });
+
+        // Hacker News links handed to the app, whether from the desktop or from a second instance,
+        // go through the same app actions the widgets use to open items, users and sites
+        application.connect_open(|app, files, _| {
+            app.activate();
+
+            for file in files {
+                match DeepLink::parse(&file.uri()) {
+                    Some(DeepLink::Item(id)) => app.activate_action("open-item", Some(&id.to_variant())),
+                    Some(DeepLink::User(user)) => app.activate_action("open-user", Some(&user.to_variant())),
+                    Some(DeepLink::Site(domain)) => app.activate_action("open-site", Some(&domain.to_variant())),
+                    None => println!("Cannot open {}", file.uri()),
+                }
+            }
+        });
+
         application.connect_activate(move |app| {
+            // activating the running app again, like when it is handed a link, only brings its window back up
+            if let Some(window) = app.active_window() {
+                window.present();
+                return;
+            }
+
             let (sender, receiver) = async_channel::bounded::<Event>(1);
 
             let client = Client::builder().use_rustls_tls().build().unwrap();
+            set_image_client(client.clone());
+
+            let settings = settings();
+
+            // pick up where the previous session left off
+            let session = Session::load();
 
-            // start fetching stories from the Hacker News API in parallel with the user interface being constructed 
+            // what the feed page currently lists, which starts out as the one left open last time,
+            // or the feed chosen in the settings
+            let source = Rc::new(RefCell::new(session.source.clone().unwrap_or_else(|| {
+                FeedSource::Feed(Feed::from_name(&settings.string("default-feed")))
+            })));
+
+            watch_data_saver(&settings);
+            apply_network_settings(&settings);
+            settings.connect_changed(Some("max-concurrent-requests"), |settings, _| apply_network_settings(settings));
+            settings.connect_changed(Some("request-timeout"), |settings, _| apply_network_settings(settings));
+            settings.connect_changed(Some("cache-size"), |settings, _| apply_network_settings(settings));
+
+            let feed_fetch: Rc<CurrentFetch> = Rc::default();
+            let comments_fetch: Rc<CurrentFetch> = Rc::default();
+
+            // start fetching stories from the Hacker News API in parallel with the user interface being constructed
             // speed is key for a mobile app, and this way the user has to wait less time before the content appears
-            spawn_cards_fetch_and_send(&sender, &client);
+            spawn_feed_fetch(&sender, &client, &source.borrow(), &settings, &feed_fetch);
 
             // initialize the application screens
             let feed_page: FeedPage = FeedPage::new();
-            
+            feed_page.set_prefetcher(CommentPrefetcher::new(client.clone()));
+            feed_page.show_source(&source.borrow());
+            feed_page.begin_stories(page_size(&settings) as usize);
+            feed_page.restore_scroll_offset(session.feed_scroll_offset);
+
             // setup listener to react when the feed page wants to refresh the feed
             //feed_page.connect_closure("fetch-cards", false, closure_local!(@strong sender => move |_: FeedPage| {
             //    spawn_cards_fetch_and_send(&sender, &client);
             //}));
 
+            // opening a story from the feed goes through the async channel like every other event
+            feed_page.connect_story_activated(clone!(
+                #[strong]
+                sender,
+                move |_, id| send_event(&sender, Event::ClickedStory(id))
+            ));
+
+            // items, users and sites can be opened from anywhere in the app through these actions
+            let open_item = gio::ActionEntry::builder("open-item")
+                .parameter_type(Some(VariantTy::UINT32))
+                .activate(clone!(
+                    #[strong]
+                    sender,
+                    move |_: &Application, _, parameter| {
+                        if let Some(id) = parameter.and_then(|parameter| parameter.get::<u32>()) {
+                            send_event(&sender, Event::OpenItem(id));
+                        }
+                    }
+                ))
+                .build();
+            let open_user = gio::ActionEntry::builder("open-user")
+                .parameter_type(Some(VariantTy::STRING))
+                .activate(clone!(
+                    #[strong]
+                    sender,
+                    move |_: &Application, _, parameter| {
+                        if let Some(user) = parameter.and_then(|parameter| parameter.get::<String>()) {
+                            send_event(&sender, Event::OpenUser(user));
+                        }
+                    }
+                ))
+                .build();
+            let open_site = gio::ActionEntry::builder("open-site")
+                .parameter_type(Some(VariantTy::STRING))
+                .activate(clone!(
+                    #[strong]
+                    sender,
+                    move |_: &Application, _, parameter| {
+                        if let Some(domain) = parameter.and_then(|parameter| parameter.get::<String>()) {
+                            send_event(&sender, Event::OpenSource(FeedSource::Site(domain)));
+                        }
+                    }
+                ))
+                .build();
+            let show_feed = gio::ActionEntry::builder("show-feed")
+                .parameter_type(Some(VariantTy::STRING))
+                .activate(clone!(
+                    #[strong]
+                    sender,
+                    move |_: &Application, _, parameter| {
+                        if let Some(name) = parameter.and_then(|parameter| parameter.get::<String>()) {
+                            send_event(&sender, Event::OpenSource(FeedSource::Feed(Feed::from_name(&name))));
+                        }
+                    }
+                ))
+                .build();
+            let search = gio::ActionEntry::builder("search")
+                .parameter_type(Some(VariantTy::STRING))
+                .activate(clone!(
+                    #[strong]
+                    sender,
+                    move |_: &Application, _, parameter| {
+                        if let Some(query) = parameter.and_then(|parameter| parameter.get::<String>()) {
+                            send_event(&sender, Event::OpenSource(FeedSource::Search(query)));
+                        }
+                    }
+                ))
+                .build();
+            let open_feed = gio::ActionEntry::builder("open-feed")
+                .activate(clone!(
+                    #[strong]
+                    sender,
+                    move |_: &Application, _, _| {
+                        let feed = Feed::from_name(&settings().string("default-feed"));
+                        send_event(&sender, Event::OpenSource(FeedSource::Feed(feed)));
+                    }
+                ))
+                .build();
+            // fetches what the feed page lists again, like after requests for it failed
+            let refresh = gio::ActionEntry::builder("refresh")
+                .activate(clone!(
+                    #[strong]
+                    sender,
+                    #[strong]
+                    client,
+                    #[strong]
+                    source,
+                    #[strong]
+                    feed_fetch,
+                    move |_: &Application, _, _| {
+                        spawn_feed_fetch(&sender, &client, &source.borrow(), &settings(), &feed_fetch)
+                    }
+                ))
+                .build();
+            // opens a story by id without resolving it first, like the saved stories, which may be opened offline
+            let open_story = gio::ActionEntry::builder("open-story")
+                .parameter_type(Some(VariantTy::UINT32))
+                .activate(clone!(
+                    #[strong]
+                    sender,
+                    move |_: &Application, _, parameter| {
+                        if let Some(id) = parameter.and_then(|parameter| parameter.get::<u32>()) {
+                            send_event(&sender, Event::ClickedStory(id));
+                        }
+                    }
+                ))
+                .build();
+            // saves the stories with the given ids for offline reading, from a card or the whole feed
+            let save_offline = gio::ActionEntry::builder("save-offline")
+                .parameter_type(Some(VariantTy::new("au").expect("\"au\" is a valid variant type.")))
+                .activate(clone!(
+                    #[strong]
+                    sender,
+                    #[strong]
+                    client,
+                    move |_: &Application, _, parameter| {
+                        if let Some(story_ids) = parameter.and_then(|parameter| parameter.get::<Vec<u32>>()) {
+                            if !story_ids.is_empty() {
+                                spawn_offline_save_and_send(&sender, &client, story_ids);
+                            }
+                        }
+                    }
+                ))
+                .build();
+            let show_offline = gio::ActionEntry::builder("show-offline")
+                .activate(clone!(
+                    #[strong]
+                    sender,
+                    move |_: &Application, _, _| send_event(&sender, Event::ShowOffline)
+                ))
+                .build();
+            app.add_action_entries([
+                open_item,
+                open_user,
+                open_site,
+                show_feed,
+                search,
+                open_feed,
+                refresh,
+                open_story,
+                save_offline,
+                show_offline,
+            ]);
+
+            // switching feeds in the preferences goes back to the feed, and changing the page size fetches it again
+            settings.connect_changed(
+                Some("default-feed"),
+                clone!(
+                    #[strong]
+                    sender,
+                    move |settings, _| {
+                        let feed = Feed::from_name(&settings.string("default-feed"));
+                        send_event(&sender, Event::OpenSource(FeedSource::Feed(feed)));
+                    }
+                ),
+            );
+            settings.connect_changed(
+                Some("page-size"),
+                clone!(
+                    #[strong]
+                    sender,
+                    #[strong]
+                    client,
+                    #[strong]
+                    source,
+                    #[strong]
+                    feed_fetch,
+                    move |settings, _| spawn_feed_fetch(&sender, &client, &source.borrow(), settings, &feed_fetch)
+                ),
+            );
+
+            // refresh the feed in the background as often as the user has asked for
+            let refresh_source: Rc<RefCell<Option<glib::SourceId>>> = Rc::default();
+            let schedule_refresh = clone!(
+                #[strong]
+                sender,
+                #[strong]
+                client,
+                #[strong]
+                refresh_source,
+                #[strong]
+                source,
+                #[strong]
+                feed_fetch,
+                move |settings: &gio::Settings| {
+                    if let Some(source) = refresh_source.take() {
+                        source.remove();
+                    }
+
+                    let minutes = settings.uint("refresh-interval");
+                    if minutes > 0 {
+                        let settings = settings.clone();
+                        let timeout_source = glib::timeout_add_seconds_local(minutes * 60, clone!(
+                            #[strong]
+                            sender,
+                            #[strong]
+                            client,
+                            #[strong]
+                            source,
+                            #[strong]
+                            feed_fetch,
+                            move || {
+                                // polling is the first thing to go when saving data
+                                if !is_data_saver_active() {
+                                    spawn_feed_refresh(&sender, &client, &source.borrow(), &settings, &feed_fetch);
+                                }
+                                glib::ControlFlow::Continue
+                            }
+                        ));
+                        refresh_source.replace(Some(timeout_source));
+                    }
+                }
+            );
+            schedule_refresh(&settings);
+            settings.connect_changed(Some("refresh-interval"), move |settings, _| schedule_refresh(settings));
+
             let story_page: StoryPage = StoryPage::new();
+            let user_page: UserPage = UserPage::new();
+            let offline_page: OfflinePage = OfflinePage::new();
 
+            // the app icon is bundled in the resources, so windows get it even when the app isn't installed
+            gtk::Window::set_default_icon_name(APP_ID);
             let window = GliderCloneWindow::new(app);
 
             let provider = gtk::CssProvider::new();
@@ -55,20 +501,202 @@ impl App {
                     gtk::STYLE_PROVIDER_PRIORITY_APPLICATION,
                 );
 
-            window.add_nav_page(&feed_page.borrow());
+            // the font and text scales live in their own provider so it can be swapped out whenever they change
+            let font_scale_provider = gtk::CssProvider::new();
+            gtk::style_context_add_provider_for_display(
+                &gtk::gdk::Display::default().expect("Could not connect to a display."),
+                &font_scale_provider,
+                gtk::STYLE_PROVIDER_PRIORITY_APPLICATION,
+            );
+            apply_font_scale(&settings, &font_scale_provider);
+            for key in ["font-scale", "text-scale"] {
+                settings.connect_changed(Some(key), clone!(
+                    #[strong]
+                    font_scale_provider,
+                    move |settings, _| apply_font_scale(settings, &font_scale_provider)
+                ));
+            }
+
+            apply_theme(&settings);
+            settings.connect_changed(Some("theme"), |settings, _| apply_theme(settings));
+
+            // the pure black variant follows the dark mode, whether the user or the system picked it
+            apply_true_black(&settings, &window);
+            settings.connect_changed(Some("true-black"), clone!(
+                #[weak]
+                window,
+                move |settings, _| apply_true_black(settings, &window)
+            ));
+            adw::StyleManager::default().connect_dark_notify(clone!(
+                #[weak]
+                window,
+                #[strong]
+                settings,
+                move |_| apply_true_black(&settings, &window)
+            ));
+
+            apply_card_density(&settings, &window);
+            settings.connect_changed(Some("card-density"), clone!(
+                #[weak]
+                window,
+                move |settings, _| apply_card_density(settings, &window)
+            ));
+
+            // keep labels like "5 minutes ago" from going stale while the app stays open
+            glib::timeout_add_seconds_local(
+                TIME_REFRESH_INTERVAL,
+                clone!(
+                    #[weak]
+                    feed_page,
+                    #[weak]
+                    story_page,
+                    #[upgrade_or]
+                    glib::ControlFlow::Break,
+                    move || {
+                        feed_page.refresh_times();
+                        story_page.refresh_times();
+                        glib::ControlFlow::Continue
+                    }
+                ),
+            );
+
+            window.set_feed_page(&feed_page);
+            window.set_story_page(&story_page);
+            window.set_settings(settings);
+            set_keyboard_shortcuts(app);
+
+            if let Some(story) = session.story {
+                story_page.restore_story(story.id, story.collapsed_ids, story.scroll_offset);
+                feed_page.select_story(story.id);
+                spawn_comments_fetch(&sender, &client, story.id, &comments_fetch);
+                if story.shown {
+                    window.show_story_page();
+                }
+            }
+            if let Some(user_id) = session.user_id {
+                send_event(&sender, Event::OpenUser(user_id));
+            }
+
+            // the session is saved when the window is closed, and when it is minimized or sent to the background,
+            // since a phone may well end the app without it ever being closed
+            let save_current_session = clone!(
+                #[weak]
+                window,
+                #[weak]
+                feed_page,
+                #[weak]
+                story_page,
+                #[weak]
+                user_page,
+                #[strong]
+                source,
+                move || save_session(&window, &feed_page, &story_page, &user_page, &source.borrow())
+            );
+            let save_current_session = Rc::new(save_current_session);
+            window.connect_close_request(clone!(
+                #[strong]
+                save_current_session,
+                move |_| {
+                    save_current_session();
+                    glib::Propagation::Proceed
+                }
+            ));
+            window.connect_suspended_notify(move |window| {
+                if window.is_suspended() {
+                    save_current_session();
+                }
+            });
+
             window.present();
 
             let event_handler = async move {
                 // the user interface has now been initialized.
-                // we now wait to recieve a Vec<StoryData> on the async channel, 
+                // we now wait to recieve stories one by one on the async channel,
                 // then construct the card widgets and add them to the view to be displayed
                 while let Ok(event) = receiver.recv().await {
                     match event {
-                        Event::SentStoryData(story_data_vec) => {
-                            feed_page.setup_cards(story_data_vec);
+                        // events from a batch that has since been superseded may still be on the channel
+                        Event::StartedStories(batch, count) if feed_fetch.is_current(batch) => {
+                            if feed_fetch.is_background() {
+                                feed_page.begin_refresh();
+                            } else {
+                                feed_page.begin_stories(count);
+                            }
+                        },
+                        Event::SentStory(batch, story_data) if feed_fetch.is_current(batch) => {
+                            feed_page.add_story(story_data);
+                        },
+                        // a background refresh that fails is left for the next one to try again, without bothering the user
+                        Event::FinishedStories(batch, failures) if feed_fetch.is_current(batch) => {
+                            if feed_fetch.is_background() {
+                                feed_page.finish_refresh(failures == 0);
+                            } else {
+                                feed_page.finish_stories();
+                                if failures > 0 {
+                                    window.add_toast(fetch_failure_toast(failures));
+                                }
+                            }
+                        },
+                        Event::FailedStories(batch, failures) if feed_fetch.is_current(batch) => {
+                            if feed_fetch.is_background() {
+                                feed_page.finish_refresh(false);
+                            } else {
+                                feed_page.finish_stories();
+                                window.add_toast(load_failure_toast(failures));
+                            }
+                        },
+                        Event::StartedStories(..)
+                        | Event::SentStory(..)
+                        | Event::FinishedStories(..)
+                        | Event::FailedStories(..) => {},
+                        Event::ClickedStory(id) => {
+                            story_page.load_story(id, None);
+                            spawn_comments_fetch(&sender, &client, id, &comments_fetch);
+                            window.show_story_page();
+                        },
+                        Event::SentComments(story_data, comment_data_vec) => {
+                            let id = story_data.id;
+                            if story_page.setup_comments(story_data, comment_data_vec) {
+                                feed_page.mark_comments_seen(id);
+                            }
+                        },
+                        Event::SentArticle(id, article) => {
+                            story_page.set_saved_article(id, article);
+                        },
+                        Event::OpenItem(id) => {
+                            spawn_item_resolve_and_send(&sender, &client, id);
                         },
-                        Event::ClickedStory() => {
-                            window.push_nav_page(&story_page.borrow());
+                        Event::ResolvedItem(story_id, comment_id) => {
+                            story_page.load_story(story_id, comment_id);
+                            spawn_comments_fetch(&sender, &client, story_id, &comments_fetch);
+                            window.show_story_page();
+                        },
+                        Event::OpenUser(user_id) => {
+                            user_page.load_user(&user_id);
+                            spawn_user_fetch_and_send(&sender, &client, user_id);
+                            window.push_nav_page(user_page.upcast_ref());
+                        },
+                        Event::SentUser(user_data) => {
+                            user_page.setup_user(user_data);
+                        },
+                        Event::OpenSource(new_source) => {
+                            feed_page.show_source(&new_source);
+                            feed_page.begin_stories(page_size(window.settings()) as usize);
+                            source.replace(new_source);
+                            spawn_feed_fetch(&sender, &client, &source.borrow(), window.settings(), &feed_fetch);
+                            window.show_feed_page();
+                        },
+                        Event::ShowOffline => {
+                            window.push_nav_page(offline_page.upcast_ref());
+                        },
+                        Event::OfflineProgress(done, total) => {
+                            window.show_offline_progress(done, total);
+                        },
+                        Event::SavedOffline(saved_story) => {
+                            offline_page.add_saved(saved_story);
+                        },
+                        Event::FinishedOffline(saved, failures) => {
+                            window.finish_offline_progress(saved, failures);
                         },
                     }
                 }
@@ -79,4 +707,174 @@ impl App {
         });
         application
     }
-}
\ No newline at end of file
+}
+
+// starts fetching the number of stories chosen in the settings from what the feed page lists,
+// cancelling the batch fetched so far
+fn spawn_feed_fetch(
+    sender: &Sender<Event>,
+    client: &Client,
+    source: &FeedSource,
+    settings: &gio::Settings,
+    feed_fetch: &CurrentFetch,
+) {
+    let batch = feed_fetch.supersede();
+    let count = page_size(settings) as usize;
+    feed_fetch.set_abort_handle(spawn_cards_fetch_and_send(sender, client, source.clone(), count, batch));
+}
+
+// fetches the feed again like `spawn_feed_fetch`, but for merging into the stories listed once all of them are in
+fn spawn_feed_refresh(
+    sender: &Sender<Event>,
+    client: &Client,
+    source: &FeedSource,
+    settings: &gio::Settings,
+    feed_fetch: &CurrentFetch,
+) {
+    spawn_feed_fetch(sender, client, source, settings, feed_fetch);
+    feed_fetch.background.set(true);
+}
+
+// starts fetching the comments of a story, cancelling those of the story opened before it
+fn spawn_comments_fetch(sender: &Sender<Event>, client: &Client, story_id: u32, comments_fetch: &CurrentFetch) {
+    comments_fetch.supersede();
+    comments_fetch.set_abort_handle(spawn_comments_fetch_and_send(sender, client, story_id));
+}
+
+// lets the user know part of the feed is missing, and offers to fetch it again
+fn fetch_failure_toast(failures: usize) -> adw::Toast {
+    let title = fill(
+        &ngettext("{} request failed", "{} requests failed", failures as u32),
+        &[&failures.to_string()],
+    );
+
+    adw::Toast::builder()
+        .title(title)
+        .button_label(gettext("Retry"))
+        .action_name("app.refresh")
+        .build()
+}
+
+// lets the user know the feed couldn't be loaded at all
+fn load_failure_toast(failures: usize) -> adw::Toast {
+    let title = fill(
+        &ngettext(
+            "Could not load any stories, {} request failed",
+            "Could not load any stories, {} requests failed",
+            failures as u32,
+        ),
+        &[&failures.to_string()],
+    );
+
+    adw::Toast::builder()
+        .title(title)
+        .button_label(gettext("Retry"))
+        .action_name("app.refresh")
+        .build()
+}
+
+fn apply_network_settings(settings: &gio::Settings) {
+    set_max_concurrent_requests(settings.uint("max-concurrent-requests") as usize);
+    set_request_timeout(settings.uint("request-timeout") as u64);
+
+    // the disk cache may have to shrink to fit
+    set_cache_size(settings.uint("cache-size") as u64);
+    http_cache().trim();
+}
+
+// records the feed, the open story and pushed page, and the scroll offsets, so the next start can restore them.
+// the data usage counted since it was last written out is saved along with them
+fn save_session(
+    window: &GliderCloneWindow,
+    feed_page: &FeedPage,
+    story_page: &StoryPage,
+    user_page: &UserPage,
+    source: &FeedSource,
+) {
+    let story = story_page.story_id().map(|id| SessionStory {
+        id,
+        shown: window.is_story_page_shown(),
+        collapsed_ids: story_page.collapsed_ids(),
+        scroll_offset: story_page.scroll_offset(),
+    });
+    let user_id = (window.visible_page_tag().as_deref() == Some("user")).then(|| user_page.user_id());
+
+    Session {
+        source: Some(source.clone()),
+        feed_scroll_offset: feed_page.scroll_offset(),
+        story,
+        user_id,
+    }
+    .save();
+
+    save_data_usage();
+}
+
+// sends an event on the async channel from code that can't wait for it to be received
+fn send_event(sender: &Sender<Event>, event: Event) {
+    glib::spawn_future_local(clone!(
+        #[strong]
+        sender,
+        async move {
+            sender
+                .send(event)
+                .await
+                .expect("The channel needs to be open.");
+        }
+    ));
+}
+
+// the keys of the window's actions, which the shortcuts window in the resources lists
+fn set_keyboard_shortcuts(app: &Application) {
+    let shortcuts: [(&str, &[&str]); 10] = [
+        ("win.next-story", &["j"]),
+        ("win.previous-story", &["k"]),
+        ("win.open-link", &["o"]),
+        ("win.open-comments", &["c"]),
+        ("win.upvote", &["u"]),
+        ("win.bookmark", &["b"]),
+        ("win.next-comment", &["n"]),
+        ("win.previous-comment", &["p"]),
+        ("win.go-back", &["BackSpace"]),
+        ("win.preferences", &["<Control>comma"]),
+    ];
+    for (action_name, accels) in shortcuts {
+        app.set_accels_for_action(action_name, accels);
+    }
+}
+
+fn apply_theme(settings: &gio::Settings) {
+    let color_scheme = match settings.string("theme").as_str() {
+        "light" => adw::ColorScheme::ForceLight,
+        "dark" => adw::ColorScheme::ForceDark,
+        _ => adw::ColorScheme::Default,
+    };
+    adw::StyleManager::default().set_color_scheme(color_scheme);
+}
+
+// the text scale comes on top of the font scale, since the cards and comments are inside the window
+fn apply_font_scale(settings: &gio::Settings, provider: &gtk::CssProvider) {
+    provider.load_from_string(&format!(
+        "window {{ font-size: {:.2}em; }} .story-card, .comment-row {{ font-size: {:.2}em; }}",
+        settings.double("font-scale"),
+        settings.double("text-scale")
+    ));
+}
+
+// the colors and spacing of the variants are in style.css, under the classes set on the window here
+fn apply_true_black(settings: &gio::Settings, window: &GliderCloneWindow) {
+    let true_black = settings.boolean("true-black") && adw::StyleManager::default().is_dark();
+    set_css_class(window.upcast_ref(), "true-black", true_black);
+}
+
+fn apply_card_density(settings: &gio::Settings, window: &GliderCloneWindow) {
+    set_css_class(window.upcast_ref(), "compact", settings.string("card-density") == "compact");
+}
+
+fn set_css_class(widget: &gtk::Widget, css_class: &str, enabled: bool) {
+    if enabled {
+        widget.add_css_class(css_class);
+    } else {
+        widget.remove_css_class(css_class);
+    }
+}
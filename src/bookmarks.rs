@@ -0,0 +1,38 @@
+use crate::storage::TimestampedIds;
+
+const BOOKMARKS_FILE: &str = "bookmarks.json";
+
+// the stories the user has bookmarked, with when each was bookmarked.
+// unlike the reading history these are never pruned
+pub struct Bookmarks(TimestampedIds);
+
+impl Default for Bookmarks {
+    fn default() -> Self {
+        Bookmarks(TimestampedIds::new(BOOKMARKS_FILE, "bookmarked"))
+    }
+}
+
+impl Bookmarks {
+    pub fn load() -> Self {
+        Bookmarks(TimestampedIds::load(BOOKMARKS_FILE, "bookmarked", None))
+    }
+
+    pub fn save(&self) {
+        self.0.save();
+    }
+
+    pub fn is_bookmarked(&self, id: u32) -> bool {
+        self.0.contains(id)
+    }
+
+    // bookmarks the story, or removes the bookmark if it already had one,
+    // and returns whether the story is bookmarked now
+    pub fn toggle(&mut self, id: u32) -> bool {
+        if self.0.remove(id) {
+            false
+        } else {
+            self.0.insert(id);
+            true
+        }
+    }
+}
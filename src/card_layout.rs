@@ -0,0 +1,149 @@
+use gtk::gio::{self, prelude::SettingsExt};
+
+// where the score of a story goes on its card
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ScorePosition {
+    // in the row of details under the title, next to the comments count
+    #[default]
+    Inline,
+    // in a badge of its own beside the title, like Glider
+    Left,
+    Right,
+}
+
+impl ScorePosition {
+    pub fn from_name(name: &str) -> Self {
+        match name {
+            "left" => ScorePosition::Left,
+            "right" => ScorePosition::Right,
+            _ => ScorePosition::Inline,
+        }
+    }
+}
+
+// the picture of the site a story links to, shown on its card
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum SiteImage {
+    #[default]
+    None,
+    // a small icon in front of the title
+    Favicon,
+    // a large tile at the end of the card
+    Thumbnail,
+}
+
+impl SiteImage {
+    pub fn from_name(name: &str) -> Self {
+        match name {
+            "favicon" => SiteImage::Favicon,
+            "thumbnail" => SiteImage::Thumbnail,
+            _ => SiteImage::None,
+        }
+    }
+}
+
+// what the story cards show and where, as chosen in the preferences
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct CardLayout {
+    pub show_domain: bool,
+    pub show_author: bool,
+    pub show_time: bool,
+    pub show_score: bool,
+    pub show_comments: bool,
+    // the position of the story in its feed, like on the website
+    pub show_rank: bool,
+    pub score_position: ScorePosition,
+    pub site_image: SiteImage,
+}
+
+impl CardLayout {
+    // the settings the layout is read from, which the cards are rebuilt for whenever one of them changes
+    pub const KEYS: [&'static str; 8] = [
+        "card-show-domain",
+        "card-show-author",
+        "card-show-time",
+        "card-show-score",
+        "card-show-comments",
+        "card-show-rank",
+        "card-score-position",
+        "card-site-image",
+    ];
+
+    pub fn load(settings: &gio::Settings) -> Self {
+        CardLayout {
+            show_domain: settings.boolean("card-show-domain"),
+            show_author: settings.boolean("card-show-author"),
+            show_time: settings.boolean("card-show-time"),
+            show_score: settings.boolean("card-show-score"),
+            show_comments: settings.boolean("card-show-comments"),
+            show_rank: settings.boolean("card-show-rank"),
+            score_position: ScorePosition::from_name(&settings.string("card-score-position")),
+            site_image: SiteImage::from_name(&settings.string("card-site-image")),
+        }
+    }
+
+    // whether the score is in the row of details rather than in a badge
+    pub fn shows_inline_score(&self) -> bool {
+        self.show_score && self.score_position == ScorePosition::Inline
+    }
+
+    pub fn shows_score_badge(&self) -> bool {
+        self.show_score && self.score_position != ScorePosition::Inline
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn layout(show_score: bool, score_position: ScorePosition) -> CardLayout {
+        CardLayout {
+            show_domain: true,
+            show_author: true,
+            show_time: true,
+            show_score,
+            show_comments: true,
+            show_rank: false,
+            score_position,
+            site_image: SiteImage::None,
+        }
+    }
+
+    #[test]
+    fn score_positions_are_read_from_their_names() {
+        assert_eq!(ScorePosition::from_name("inline"), ScorePosition::Inline);
+        assert_eq!(ScorePosition::from_name("left"), ScorePosition::Left);
+        assert_eq!(ScorePosition::from_name("right"), ScorePosition::Right);
+        assert_eq!(ScorePosition::from_name("bogus"), ScorePosition::Inline);
+    }
+
+    #[test]
+    fn site_images_are_read_from_their_names() {
+        assert_eq!(SiteImage::from_name("none"), SiteImage::None);
+        assert_eq!(SiteImage::from_name("favicon"), SiteImage::Favicon);
+        assert_eq!(SiteImage::from_name("thumbnail"), SiteImage::Thumbnail);
+        assert_eq!(SiteImage::from_name(""), SiteImage::None);
+    }
+
+    #[test]
+    fn the_score_is_either_inline_or_in_a_badge() {
+        let inline = layout(true, ScorePosition::Inline);
+        assert!(inline.shows_inline_score());
+        assert!(!inline.shows_score_badge());
+
+        for position in [ScorePosition::Left, ScorePosition::Right] {
+            let badge = layout(true, position);
+            assert!(!badge.shows_inline_score());
+            assert!(badge.shows_score_badge());
+        }
+    }
+
+    #[test]
+    fn a_hidden_score_is_shown_nowhere() {
+        for position in [ScorePosition::Inline, ScorePosition::Left, ScorePosition::Right] {
+            let hidden = layout(false, position);
+            assert!(!hidden.shows_inline_score());
+            assert!(!hidden.shows_score_badge());
+        }
+    }
+}
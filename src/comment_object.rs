@@ -0,0 +1,79 @@
+use glib::Object;
+use gtk::glib;
+
+use std::cell::{Cell, RefCell};
+
+use glib::Properties;
+use gtk::prelude::*;
+use gtk::subclass::prelude::*;
+
+use crate::relative_time::time_label_markup;
+
+glib::wrapper! {
+    pub struct CommentObject(ObjectSubclass<imp::CommentObject>);
+}
+
+#[derive(Clone, Debug, Default, glib::Boxed)]
+#[boxed_type(name = "CommentData")]
+pub struct CommentData {
+    pub id: u32,
+    pub author: String,
+    // the unix timestamp the time label is worked out from, kept so the label can be brought up to date
+    pub time: Option<i64>,
+    pub time_formatted: String,
+    pub text: String,
+    pub depth: u32,
+    // the comment this one replies to, or the story for top-level comments
+    pub parent: u32,
+}
+
+impl CommentObject {
+    pub fn new(comment_data: CommentData) -> Self {
+        Object::builder()
+            .property("data", comment_data)
+            .build()
+    }
+
+    // works the "n units ago" label out again, so it doesn't go stale while the comment stays on screen
+    pub fn refresh_time(&self) {
+        let time = self.imp().data.borrow().time;
+        self.set_time_formatted(time_label_markup(time));
+    }
+}
+
+mod imp {
+    use super::*;
+
+    #[derive(Properties, Default)]
+    #[properties(wrapper_type = super::CommentObject)]
+    pub struct CommentObject {
+        #[property(get, set, construct_only)]
+        #[property(name = "id", get, type = u32, member = id)]
+        #[property(name = "author", get, set, type = String, member = author)]
+        #[property(name = "time-formatted", get, set, type = String, member = time_formatted)]
+        #[property(name = "text", get, set, type = String, member = text)]
+        #[property(name = "depth", get, set, type = u32, member = depth)]
+        #[property(name = "parent", get, type = u32, member = parent)]
+        pub data: RefCell<CommentData>,
+        // whether the comment was posted since the user last opened the story
+        #[property(get, set)]
+        pub unseen: Cell<bool>,
+        // whether the user has folded away the replies to the comment
+        #[property(get, set)]
+        pub collapsed: Cell<bool>,
+        // whether the comment is folded away inside a collapsed ancestor
+        #[property(get, set)]
+        pub hidden: Cell<bool>,
+    }
+
+    // The central trait for subclassing a GObject
+    #[glib::object_subclass]
+    impl ObjectSubclass for CommentObject {
+        const NAME: &'static str = "CommentObject";
+        type Type = super::CommentObject;
+    }
+
+    // Trait shared by all GObjects
+    #[glib::derived_properties]
+    impl ObjectImpl for CommentObject {}
+}
@@ -0,0 +1,366 @@
+use glib::subclass::{
+    types::{ObjectSubclass, ObjectSubclassExt},
+    InitializingObject,
+};
+use chrono::Utc;
+use gettextrs::gettext;
+use gtk::glib::Object;
+use gtk::subclass::box_::BoxImpl;
+use gtk::subclass::widget::WidgetClassExt;
+use gtk::subclass::{
+    prelude::{ObjectImpl, ObjectImplExt},
+    widget::{CompositeTemplateClass, CompositeTemplateInitializingExt, WidgetImpl},
+};
+use gtk::CompositeTemplate;
+use gtk::{
+    accessible, gdk,
+    gio,
+    glib::{self, clone, ToVariant},
+    prelude::{AccessibleExt, GestureExt, ObjectExt, PopoverExt, WidgetExt},
+    subclass::prelude::ObjectSubclassIsExt,
+};
+use gtk::{EventSequenceState, GestureClick, GestureLongPress, Image, Label, PopoverMenu, TemplateChild};
+use std::cell::RefCell;
+
+use crate::comment_object::CommentObject;
+use crate::i18n::fill;
+use crate::launcher::open_uri;
+use crate::network::website_reply_url;
+use crate::relative_time::format_relative_time;
+use crate::share::copy_text;
+
+// how far each level of replies is indented, in pixels
+const INDENT_PER_DEPTH: i32 = 12;
+
+glib::wrapper! {
+    pub struct CommentRow(ObjectSubclass<imp::CommentRow>)
+        @extends gtk::Box, gtk::Widget,
+        @implements gtk::Accessible, gtk::Buildable,
+                    gtk::ConstraintTarget, gtk::Orientable;
+}
+
+impl Default for CommentRow {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl CommentRow {
+    pub fn new() -> Self {
+        Object::builder().build()
+    }
+
+    pub fn bind(&self, comment_object: &CommentObject) {
+        let author_label = self.imp().author_label.get();
+        let time_formatted_label = self.imp().time_formatted_label.get();
+        let text_label = self.imp().text_label.get();
+        let mut bindings = self.imp().bindings.borrow_mut();
+
+        bindings.push(
+            comment_object
+                .bind_property("author", &author_label, "label")
+                .sync_create()
+                .build(),
+        );
+
+        bindings.push(
+            comment_object
+                .bind_property("time-formatted", &time_formatted_label, "label")
+                .sync_create()
+                .build(),
+        );
+
+        bindings.push(
+            comment_object
+                .bind_property("text", &text_label, "label")
+                .sync_create()
+                .build(),
+        );
+
+        // a collapsed comment keeps only its header
+        bindings.push(
+            comment_object
+                .bind_property("collapsed", &text_label, "visible")
+                .invert_boolean()
+                .sync_create()
+                .build(),
+        );
+
+        bindings.push(
+            comment_object
+                .bind_property("collapsed", &self.imp().collapse_icon.get(), "icon-name")
+                .transform_to(|_, collapsed: bool| {
+                    Some(if collapsed { "pan-end-symbolic" } else { "pan-down-symbolic" })
+                })
+                .sync_create()
+                .build(),
+        );
+
+        bindings.push(
+            comment_object
+                .bind_property("depth", self, "margin-start")
+                .transform_to(|_, depth: u32| Some(8 + depth as i32 * INDENT_PER_DEPTH))
+                .sync_create()
+                .build(),
+        );
+
+        let mut handlers = self.imp().handlers.borrow_mut();
+
+        // show the accent bar for as long as the comment counts as new
+        self.set_unseen_style(comment_object.unseen());
+        handlers.push(comment_object.connect_unseen_notify(clone!(
+            #[weak(rename_to = comment_row)]
+            self,
+            move |comment_object| comment_row.set_unseen_style(comment_object.unseen())
+        )));
+
+        // the bindings above are notified first, so the text label is already up to date here
+        self.update_accessible_properties(comment_object);
+        handlers.push(comment_object.connect_notify_local(
+            None,
+            clone!(
+                #[weak(rename_to = comment_row)]
+                self,
+                move |comment_object, _| comment_row.update_accessible_properties(comment_object)
+            ),
+        ));
+
+        self.imp().comment_object.replace(Some(comment_object.clone()));
+
+        // top-level comments reply to the story itself
+        self.action_set_enabled("comment.view-parent", comment_object.depth() > 0);
+    }
+
+    pub fn unbind(&self) {
+        // Unbind all stored bindings
+        for binding in self.imp().bindings.borrow_mut().drain(..) {
+            binding.unbind();
+        }
+
+        let handlers: Vec<_> = self.imp().handlers.borrow_mut().drain(..).collect();
+        if let Some(comment_object) = self.imp().comment_object.take() {
+            for handler in handlers {
+                comment_object.disconnect(handler);
+            }
+        }
+    }
+
+    // screen readers announce who wrote the comment, when and how deep in the thread it is,
+    // followed by its text unless it is collapsed
+    fn update_accessible_properties(&self, comment_object: &CommentObject) {
+        let level = comment_object.depth() + 1;
+        let time = format_relative_time(comment_object.data().time, Utc::now());
+        let label = fill(
+            // Translators: {0} is the author's name, {1} how long ago they wrote the comment, {2} its depth in the thread
+            &gettext("Comment by {0}, {1}, level {2}"),
+            &[&comment_object.author(), &time, &level.to_string()],
+        );
+        let description = if comment_object.collapsed() {
+            String::new()
+        } else {
+            self.imp().text_label.text().to_string()
+        };
+
+        self.update_property(&[
+            accessible::Property::Label(&label),
+            accessible::Property::Level(level as i32),
+            accessible::Property::Description(&description),
+        ]);
+        self.update_state(&[accessible::State::Expanded(Some(!comment_object.collapsed()))]);
+    }
+
+    // the `CommentObject` currently shown by this row, if any
+    pub fn comment_object(&self) -> Option<CommentObject> {
+        self.imp().comment_object.borrow().clone()
+    }
+
+    fn set_unseen_style(&self, unseen: bool) {
+        if unseen {
+            self.add_css_class("new-comment");
+        } else {
+            self.remove_css_class("new-comment");
+        }
+    }
+
+    // long-pressing or right-clicking a comment opens a menu of actions for it
+    fn setup_context_menu(&self) {
+        let context_menu = PopoverMenu::from_model(Some(&self.imp().comment_menu.get()));
+        context_menu.set_parent(self);
+        self.imp().context_menu.replace(Some(context_menu));
+
+        let right_click = GestureClick::builder().button(gdk::BUTTON_SECONDARY).build();
+        right_click.connect_pressed(clone!(
+            #[weak(rename_to = comment_row)]
+            self,
+            move |gesture, _, x, y| {
+                gesture.set_state(EventSequenceState::Claimed);
+                comment_row.show_context_menu(x, y);
+            }
+        ));
+        self.add_controller(right_click);
+
+        let long_press = GestureLongPress::builder().touch_only(true).build();
+        long_press.connect_pressed(clone!(
+            #[weak(rename_to = comment_row)]
+            self,
+            move |gesture, x, y| {
+                gesture.set_state(EventSequenceState::Claimed);
+                comment_row.show_context_menu(x, y);
+            }
+        ));
+        self.add_controller(long_press);
+    }
+
+    // clicking or tapping the header of a comment collapses it
+    fn setup_header_click(&self) {
+        let click = GestureClick::builder().button(gdk::BUTTON_PRIMARY).build();
+        click.connect_released(clone!(
+            #[weak(rename_to = comment_row)]
+            self,
+            move |gesture, _, _, _| {
+                gesture.set_state(EventSequenceState::Claimed);
+                comment_row.toggle_collapsed();
+            }
+        ));
+        self.imp().header_box.add_controller(click);
+    }
+
+    fn show_context_menu(&self, x: f64, y: f64) {
+        if let Some(context_menu) = self.imp().context_menu.borrow().as_ref() {
+            context_menu.set_pointing_to(Some(&gdk::Rectangle::new(x as i32, y as i32, 1, 1)));
+            context_menu.popup();
+        }
+    }
+
+    // from the keyboard, the menu opens under the comment's header
+    fn show_context_menu_from_keyboard(&self) -> glib::Propagation {
+        let header_box = self.imp().header_box.get();
+        self.show_context_menu(header_box.width() as f64 / 2.0, header_box.height() as f64);
+        glib::Propagation::Stop
+    }
+
+    // which comments are left visible is up to the story page holding the thread
+    fn toggle_collapsed(&self) {
+        let id = self.imp().comment_object.borrow().as_ref().map(|comment_object| comment_object.id());
+        if let Some(id) = id {
+            let _ = self.activate_action("story.toggle-collapsed", Some(&id.to_variant()));
+        }
+    }
+
+    // copies the comment as it is shown, without the markup
+    fn copy_text(&self) {
+        copy_text(self, &self.imp().text_label.text(), &gettext("Comment copied"));
+    }
+
+    // the API is read-only, so replies have to be written on the website where the user is logged in
+    fn reply(&self) {
+        if let Some(comment_object) = self.imp().comment_object.borrow().as_ref() {
+            open_uri(self, &website_reply_url(comment_object.id()));
+        }
+    }
+
+    // scrolling to another comment is up to the story page holding the thread
+    fn view_parent(&self) {
+        let parent = self.imp().comment_object.borrow().as_ref().map(|comment_object| comment_object.parent());
+        if let Some(parent) = parent {
+            let _ = self.activate_action("story.view-comment", Some(&parent.to_variant()));
+        }
+    }
+
+    fn view_author(&self) {
+        let author = self.imp().comment_object.borrow().as_ref().map(|comment_object| comment_object.author());
+        if let Some(author) = author {
+            let _ = self.activate_action("app.open-user", Some(&author.to_variant()));
+        }
+    }
+}
+
+mod imp {
+    use glib::{Binding, SignalHandlerId};
+
+    use super::*;
+
+    // Object holding the state
+    #[derive(CompositeTemplate, Default)]
+    #[template(file = "src/ui/comment_row.blp")]
+    pub struct CommentRow {
+        #[template_child]
+        pub header_box: TemplateChild<gtk::Box>,
+        #[template_child]
+        pub collapse_icon: TemplateChild<Image>,
+        #[template_child]
+        pub author_label: TemplateChild<Label>,
+        #[template_child]
+        pub time_formatted_label: TemplateChild<Label>,
+        #[template_child]
+        pub text_label: TemplateChild<Label>,
+        #[template_child]
+        pub comment_menu: TemplateChild<gio::MenuModel>,
+        pub context_menu: RefCell<Option<PopoverMenu>>,
+        pub bindings: RefCell<Vec<Binding>>,
+        pub comment_object: RefCell<Option<CommentObject>>,
+        pub handlers: RefCell<Vec<SignalHandlerId>>,
+    }
+
+    // The central trait for subclassing a GObject
+    #[glib::object_subclass]
+    impl ObjectSubclass for CommentRow {
+        // `NAME` needs to match `class` attribute of template
+        const NAME: &'static str = "CommentRow";
+        type Type = super::CommentRow;
+        type ParentType = gtk::Box;
+
+        fn class_init(klass: &mut Self::Class) {
+            klass.bind_template();
+
+            klass.install_action("comment.toggle-collapsed", None, |comment_row, _, _| {
+                comment_row.toggle_collapsed()
+            });
+            klass.install_action("comment.copy-text", None, |comment_row, _, _| comment_row.copy_text());
+            klass.install_action("comment.reply", None, |comment_row, _, _| comment_row.reply());
+            klass.install_action("comment.view-parent", None, |comment_row, _, _| comment_row.view_parent());
+            klass.install_action("comment.view-author", None, |comment_row, _, _| comment_row.view_author());
+
+            klass.set_accessible_role(gtk::AccessibleRole::Comment);
+            for key in [gdk::Key::Return, gdk::Key::KP_Enter, gdk::Key::space] {
+                klass.add_binding_action(key, gdk::ModifierType::empty(), "comment.toggle-collapsed");
+            }
+            klass.add_binding(gdk::Key::Menu, gdk::ModifierType::empty(), |comment_row| {
+                comment_row.show_context_menu_from_keyboard()
+            });
+            klass.add_binding(gdk::Key::F10, gdk::ModifierType::SHIFT_MASK, |comment_row| {
+                comment_row.show_context_menu_from_keyboard()
+            });
+        }
+
+        fn instance_init(obj: &InitializingObject<Self>) {
+            obj.init_template();
+        }
+    }
+
+    // Trait shared by all GObjects
+    impl ObjectImpl for CommentRow {
+        fn constructed(&self) {
+            // Call "constructed" on parent
+            self.parent_constructed();
+
+            // Setup
+            let obj = self.obj();
+            obj.setup_context_menu();
+            obj.setup_header_click();
+            obj.update_property(&[accessible::Property::KeyShortcuts("Enter Menu Shift+F10")]);
+        }
+
+        fn dispose(&self) {
+            if let Some(context_menu) = self.context_menu.take() {
+                context_menu.unparent();
+            }
+        }
+    }
+
+    // Trait shared by all widgets
+    impl WidgetImpl for CommentRow {}
+
+    // Trait shared by all boxes
+    impl BoxImpl for CommentRow {}
+}
@@ -0,0 +1,49 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use gtk::gio::{self, prelude::NetworkMonitorExt, prelude::SettingsExt};
+use gtk::glib::{self, clone};
+
+// the most stories fetched at once while saving data
+const DATA_SAVER_PAGE_SIZE: u32 = 10;
+
+static DATA_SAVER_ACTIVE: AtomicBool = AtomicBool::new(false);
+
+// whether to hold back on downloads, either because the user asked for it
+// or, by default, because the connection is metered like mobile data usually is.
+// while it is on, images aren't loaded, pages are smaller, comments aren't prefetched
+// and the feed isn't refreshed in the background
+pub fn is_data_saver_active() -> bool {
+    DATA_SAVER_ACTIVE.load(Ordering::Relaxed)
+}
+
+// keeps track of the preference and of whether the connection is metered from then on,
+// so the cards asking as they are bound only read a flag.
+// the settings need to be kept alive by the caller for as long as the app runs
+pub fn watch_data_saver(settings: &gio::Settings) {
+    update_data_saver(settings);
+    settings.connect_changed(Some("data-saver"), |settings, _| update_data_saver(settings));
+    gio::NetworkMonitor::default().connect_network_metered_notify(clone!(
+        #[weak]
+        settings,
+        move |_| update_data_saver(&settings)
+    ));
+}
+
+fn update_data_saver(settings: &gio::Settings) {
+    let active = match settings.string("data-saver").as_str() {
+        "on" => true,
+        "off" => false,
+        _ => gio::NetworkMonitor::default().is_network_metered(),
+    };
+    DATA_SAVER_ACTIVE.store(active, Ordering::Relaxed);
+}
+
+// the number of stories to fetch at once, which the data saver caps
+pub fn page_size(settings: &gio::Settings) -> u32 {
+    let page_size = settings.uint("page-size");
+    if is_data_saver_active() {
+        page_size.min(DATA_SAVER_PAGE_SIZE)
+    } else {
+        page_size
+    }
+}
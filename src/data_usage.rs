@@ -0,0 +1,138 @@
+use std::collections::{BTreeMap, HashMap};
+use std::sync::atomic::{AtomicI64, Ordering};
+use std::sync::{Mutex, MutexGuard, OnceLock};
+
+use chrono::{Duration, Local, NaiveDate, Utc};
+use gettextrs::gettext;
+use reqwest::header::HeaderMap;
+use serde::{Deserialize, Serialize};
+
+use crate::storage::{load_json, save_json};
+
+const DATA_USAGE_FILE: &str = "data_usage.json";
+// how many days of usage are kept
+const MAX_DAYS: i64 = 30;
+// the counts are written to disk at most this often, in seconds, since every response adds to them
+const SAVE_INTERVAL: i64 = 30;
+
+// what the bytes downloaded were spent on
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DataCategory {
+    // the story lists of feeds, sites and searches
+    Feeds,
+    // stories and comments
+    Items,
+    Users,
+    // the pages of stories saved for offline reading
+    Articles,
+    Images,
+}
+
+impl DataCategory {
+    pub const ALL: [DataCategory; 5] = [
+        DataCategory::Feeds,
+        DataCategory::Items,
+        DataCategory::Users,
+        DataCategory::Articles,
+        DataCategory::Images,
+    ];
+
+    fn key(&self) -> &'static str {
+        match self {
+            DataCategory::Feeds => "feeds",
+            DataCategory::Items => "items",
+            DataCategory::Users => "users",
+            DataCategory::Articles => "articles",
+            DataCategory::Images => "images",
+        }
+    }
+
+    pub fn title(&self) -> String {
+        match self {
+            DataCategory::Feeds => gettext("Feeds"),
+            DataCategory::Items => gettext("Stories and Comments"),
+            DataCategory::Users => gettext("Users"),
+            DataCategory::Articles => gettext("Saved Articles"),
+            DataCategory::Images => gettext("Images"),
+        }
+    }
+}
+
+// the bytes downloaded per local day and category
+#[derive(Serialize, Deserialize, Default, Clone)]
+pub struct DataUsage {
+    days: BTreeMap<NaiveDate, HashMap<String, u64>>,
+}
+
+static LAST_SAVED: AtomicI64 = AtomicI64::new(0);
+
+// responses are counted from the tokio runtime's threads, so the usage is shared behind a lock
+fn data_usage() -> MutexGuard<'static, DataUsage> {
+    static DATA_USAGE: OnceLock<Mutex<DataUsage>> = OnceLock::new();
+    DATA_USAGE
+        .get_or_init(|| Mutex::new(load_json(DATA_USAGE_FILE)))
+        .lock()
+        .expect("The data usage lock should not be poisoned.")
+}
+
+// adds the bytes a response took on the wire to today's usage.
+// the usage is written out from a copy, so other downloads aren't held up by the disk
+pub fn record_download(category: DataCategory, bytes: u64) {
+    let mut usage = data_usage();
+    let today = Local::now().date_naive();
+    *usage
+        .days
+        .entry(today)
+        .or_default()
+        .entry(category.key().to_string())
+        .or_default() += bytes;
+
+    let now = Utc::now().timestamp();
+    if now - LAST_SAVED.load(Ordering::Relaxed) >= SAVE_INTERVAL {
+        LAST_SAVED.store(now, Ordering::Relaxed);
+        usage.prune(today);
+        let snapshot = usage.clone();
+        drop(usage);
+        save_json(DATA_USAGE_FILE, &snapshot);
+    }
+}
+
+// writes out whatever has been counted since the last save, like when the app is closed
+pub fn save_data_usage() {
+    let snapshot = data_usage().clone();
+    save_json(DATA_USAGE_FILE, &snapshot);
+    LAST_SAVED.store(Utc::now().timestamp(), Ordering::Relaxed);
+}
+
+// the size of a response as it came over the network: its headers, and its body before anything was done to it
+pub fn transfer_size(headers: &HeaderMap, body_length: usize) -> u64 {
+    // every header line is its name, a colon and a space, its value and a line break
+    let header_length: usize = headers
+        .iter()
+        .map(|(name, value)| name.as_str().len() + value.len() + 4)
+        .sum();
+    (header_length + body_length) as u64
+}
+
+// the usage of the most recent days first, with the bytes spent on each category
+pub fn daily_usage() -> Vec<(NaiveDate, Vec<(DataCategory, u64)>)> {
+    data_usage()
+        .days
+        .iter()
+        .rev()
+        .map(|(day, categories)| {
+            let bytes = DataCategory::ALL
+                .iter()
+                .map(|category| (*category, categories.get(category.key()).copied().unwrap_or(0)))
+                .collect();
+            (*day, bytes)
+        })
+        .collect()
+}
+
+impl DataUsage {
+    fn prune(&mut self, today: NaiveDate) {
+        let cutoff = today - Duration::days(MAX_DAYS);
+        self.days.retain(|day, _| *day > cutoff);
+    }
+}
@@ -0,0 +1,49 @@
+use url::Url;
+
+use crate::network::is_valid_user_id;
+
+// the kinds of Hacker News links the app can open itself
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DeepLink {
+    Item(u32),
+    User(String),
+    Site(String),
+}
+
+impl DeepLink {
+    // understands links to the Hacker News website, like https://news.ycombinator.com/item?id=123,
+    // and the same pages under the app's own scheme, like hn:item?id=123 or hn://user?id=pg
+    pub fn parse(uri: &str) -> Option<DeepLink> {
+        let url = Url::parse(uri).ok()?;
+
+        let page = match url.scheme() {
+            "http" | "https" => {
+                let host = url.host_str()?;
+                if host != "news.ycombinator.com" && host != "www.news.ycombinator.com" {
+                    return None;
+                }
+                url.path().trim_matches('/').to_string()
+            }
+            // hn://item?id=123 has the page as its host, hn:item?id=123 as its path
+            "hn" => match url.host_str() {
+                Some(host) => host.to_string(),
+                None => url.path().trim_matches('/').to_string(),
+            },
+            _ => return None,
+        };
+
+        let parameter = |name: &str| {
+            url.query_pairs()
+                .find(|(key, _)| key == name)
+                .map(|(_, value)| value.trim().to_string())
+                .filter(|value| !value.is_empty())
+        };
+
+        match page.as_str() {
+            "item" => parameter("id")?.parse().ok().map(DeepLink::Item),
+            "user" => parameter("id").filter(|id| is_valid_user_id(id)).map(DeepLink::User),
+            "from" => parameter("site").map(DeepLink::Site),
+            _ => None,
+        }
+    }
+}
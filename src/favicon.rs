@@ -0,0 +1,160 @@
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex, MutexGuard, OnceLock};
+use std::time::{Duration, SystemTime};
+
+use gtk::{gdk, glib};
+use reqwest::Client;
+use tokio::sync::OnceCell;
+
+use crate::data_saver::is_data_saver_active;
+use crate::data_usage::DataCategory;
+use crate::network::{is_offline, read_body, FetchError};
+use crate::storage::cache_dir;
+use crate::transform::runtime;
+
+// icons are small, anything bigger than this is not what it claims to be
+const MAX_FAVICON_SIZE: usize = 256 * 1024;
+const FAVICON_TIMEOUT: Duration = Duration::from_secs(10);
+// sites rarely change their icon, so the one on disk is used for a month before it is fetched again
+const FAVICON_MAX_AGE: Duration = Duration::from_secs(30 * 24 * 60 * 60);
+
+// the icon of every host asked for so far, as the image file it came in, or `None` for the ones that don't have one.
+// a host being fetched has an empty cell, which the cards asking for it in the meantime wait on,
+// and which stays empty if the download failed in a way that may well go away, so it is tried again later
+type Favicon = Arc<OnceCell<Option<glib::Bytes>>>;
+
+fn favicons() -> MutexGuard<'static, HashMap<String, Favicon>> {
+    static FAVICONS: OnceLock<Mutex<HashMap<String, Favicon>>> = OnceLock::new();
+    FAVICONS
+        .get_or_init(|| Mutex::new(HashMap::new()))
+        .lock()
+        .expect("The favicon lock should not be poisoned.")
+}
+
+thread_local! {
+    // the icons turned into textures so far, which can't leave the GTK thread,
+    // so a card bound again doesn't decode its icon again
+    static TEXTURES: RefCell<HashMap<String, gdk::Texture>> = RefCell::default();
+}
+
+// the app's client, handed over on start, so the icons and thumbnails share its connections
+static CLIENT: OnceLock<Client> = OnceLock::new();
+
+pub fn set_image_client(client: Client) {
+    let _ = CLIENT.set(client);
+}
+
+// the client to download the images of sites with, unless they may not be downloaded right now,
+// in which case only the ones fetched before are shown
+pub fn image_client() -> Option<&'static Client> {
+    CLIENT.get().filter(|_| !is_data_saver_active() && !is_offline())
+}
+
+// hands the icon of a site to `on_loaded` on the GTK thread, right away if it is in memory already.
+// otherwise it is read from disk or downloaded on the tokio runtime, unless the data saver is on,
+// in which case only icons fetched before are shown. sites without one get nothing
+pub fn load_favicon(host: &str, on_loaded: impl FnOnce(gdk::Texture) + 'static) {
+    if host.is_empty() {
+        return;
+    }
+    if let Some(texture) = TEXTURES.with_borrow(|textures| textures.get(host).cloned()) {
+        on_loaded(texture);
+        return;
+    }
+
+    let favicon = favicons().entry(host.to_string()).or_default().clone();
+    if favicon.get().is_some_and(Option::is_none) {
+        return;
+    }
+
+    let host = host.to_string();
+    let client = image_client();
+    glib::spawn_future_local(async move {
+        let image = runtime().spawn(fetch_favicon(host.clone(), favicon, client)).await;
+        if let Ok(Some(image)) = image {
+            if let Ok(texture) = gdk::Texture::from_bytes(&image) {
+                TEXTURES.with_borrow_mut(|textures| textures.insert(host, texture.clone()));
+                on_loaded(texture);
+            }
+        }
+    });
+}
+
+async fn fetch_favicon(host: String, favicon: Favicon, client: Option<&'static Client>) -> Option<glib::Bytes> {
+    if let Some(image) = favicon.get() {
+        return image.clone();
+    }
+    if let Some(image) = read_favicon(&host, client.is_some()).await {
+        let _ = favicon.set(Some(image.clone()));
+        return Some(image);
+    }
+    // while saving data the host is left for later, rather than remembered as having no icon
+    let client = client?;
+
+    let image = favicon
+        .get_or_try_init(|| async {
+            match download_favicon(client, &host).await {
+                Ok(image) => Ok(Some(image)),
+                Err(e) if e.is_transient() => Err(e),
+                Err(_) => Ok(None),
+            }
+        })
+        .await;
+    image.ok().cloned().flatten()
+}
+
+// plenty of sites have no icon at the usual place, or send a page instead, which isn't worth reporting
+async fn download_favicon(client: &Client, host: &str) -> Result<glib::Bytes, FetchError> {
+    let response = client
+        .get(format!("https://{}/favicon.ico", host))
+        .timeout(FAVICON_TIMEOUT)
+        .send()
+        .await?
+        .error_for_status()?;
+    let image = glib::Bytes::from_owned(read_body(response, MAX_FAVICON_SIZE, DataCategory::Images).await?);
+    if gdk::Texture::from_bytes(&image).is_err() {
+        return Err(FetchError::Rejected(format!("The favicon of {} is not an image", host)));
+    }
+
+    if let Err(e) = tokio::fs::write(favicon_path(host), &image[..]).await {
+        println!("Could not cache favicon of {}: {}", host, e);
+    }
+
+    Ok(image)
+}
+
+// an icon past its age is only used while no newer one may be downloaded
+async fn read_favicon(host: &str, download: bool) -> Option<glib::Bytes> {
+    let path = favicon_path(host);
+    let age = tokio::fs::metadata(&path)
+        .await
+        .and_then(|metadata| metadata.modified())
+        .ok()
+        .and_then(|modified| SystemTime::now().duration_since(modified).ok())?;
+    if download && age > FAVICON_MAX_AGE {
+        return None;
+    }
+
+    tokio::fs::read(path).await.ok().map(glib::Bytes::from_owned)
+}
+
+fn favicons_dir() -> &'static Path {
+    static FAVICONS_DIR: OnceLock<PathBuf> = OnceLock::new();
+    FAVICONS_DIR.get_or_init(|| {
+        let dir = cache_dir().join("favicons");
+
+        if let Err(e) = fs::create_dir_all(&dir) {
+            println!("Could not create cache directory {}: {}", dir.display(), e);
+        }
+
+        dir
+    })
+}
+
+// host names are made of letters, digits, dots and dashes only, so they make fine file names
+fn favicon_path(host: &str) -> PathBuf {
+    favicons_dir().join(host)
+}
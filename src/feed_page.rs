@@ -3,24 +3,41 @@ use glib::subclass::{
     types::{ObjectSubclass, ObjectSubclassExt},
     InitializingObject,
 };
-use gtk::glib::Object;
-use gtk::prelude::{Cast, CastNone};
+use gettextrs::{gettext, ngettext};
+use gtk::glib::{clone, closure_local, markup_escape_text, Object};
+use gtk::prelude::{AdjustmentExt, Cast, CastNone, ListModelExt, ObjectExt, StaticType, ToVariant, WidgetExt};
 use gtk::subclass::widget::WidgetClassExt;
 use gtk::subclass::{
     prelude::{ObjectImpl, ObjectImplExt},
     widget::{CompositeTemplateClass, CompositeTemplateInitializingExt, WidgetImpl},
 };
-use gtk::{ListItem, SignalListItemFactory};
+use adw::prelude::{AdwDialogExt, NavigationPageExt};
+use gtk::gio::{self, prelude::{SettingsExt, SettingsExtManual}};
+use gtk::{
+    CustomFilter, FilterChange, FilterListModel, FlattenListModel, ListItem, ListScrollFlags, PickFlags,
+    SignalListItemFactory,
+};
 use gtk::CompositeTemplate;
-use gtk::{glib, NoSelection};
+use gtk::{glib, NoSelection, ScrolledWindow};
 use gtk::subclass::prelude::ObjectSubclassIsExt;
 use gtk::TemplateChild;
-use std::cell::RefCell;
+use std::cell::{Cell, OnceCell, RefCell};
+use std::collections::HashMap;
 
 
 use gtk::{gio::ListStore, ListView};
 use gtk::prelude::ListItemExt;
 
+use crate::bookmarks::Bookmarks;
+use crate::card_layout::CardLayout;
+use crate::hidden_stories::HiddenStories;
+use crate::i18n::{fill, format_number};
+use crate::launcher::open_uri;
+use crate::mute_filters::{MuteFilters, MuteMatcher};
+use crate::network::{website_item_url, Feed, FeedSource};
+use crate::prefetch::CommentPrefetcher;
+use crate::read_history::ReadHistory;
+use crate::seen_comments::SeenComments;
 use crate::story_card::StoryCard;
 use crate::story_object::{StoryData, StoryObject};
 
@@ -36,6 +53,10 @@ impl FeedPage {
         Object::builder().build()
     }
 
+    fn settings(&self) -> &gio::Settings {
+        self.imp().settings.get().expect("Could not get settings.")
+    }
+
     fn cards(&self) -> ListStore {
         self.imp()
             .cards
@@ -44,6 +65,23 @@ impl FeedPage {
             .expect("Could not get current cards.")
     }
 
+    // calls `f` with the id of a story whenever the user opens it from the feed
+    pub fn connect_story_activated<F: Fn(&Self, u32) + 'static>(&self, f: F) -> glib::SignalHandlerId {
+        self.connect_closure(
+            "story-activated",
+            false,
+            closure_local!(move |feed_page: FeedPage, id: u32| f(&feed_page, id)),
+        )
+    }
+
+    fn filtered(&self) -> ListStore {
+        self.imp()
+            .filtered
+            .borrow()
+            .clone()
+            .expect("Could not get filtered cards.")
+    }
+
     fn setup_model_and_view(&self) {
         // Create new model
         let model = ListStore::new::<StoryObject>();
@@ -51,84 +89,706 @@ impl FeedPage {
         // Get state and set model
         self.imp().cards.replace(Some(model));
 
+        // skeleton cards for the stories still being fetched, listed after the ones already in
+        self.imp().placeholders.replace(Some(ListStore::new::<StoryObject>()));
+
+        // stories hidden by the mute filters are kept aside so the user can peek at them
+        self.imp().filtered.replace(Some(ListStore::new::<StoryObject>()));
+        self.imp().filtered_banner.connect_button_clicked(clone!(
+            #[weak(rename_to = feed_page)]
+            self,
+            move |_| feed_page.show_filtered()
+        ));
+
+        // hide stories that have already been read, but only while the user has asked for it
+        let read_filter = CustomFilter::new(clone!(
+            #[weak(rename_to = feed_page)]
+            self,
+            #[upgrade_or]
+            true,
+            move |obj| {
+                let story_object = obj
+                    .downcast_ref::<StoryObject>()
+                    .expect("The item has to be an `StoryObject`.");
+                !(feed_page.hide_read() && story_object.read())
+            }
+        ));
+        let filter_model = FilterListModel::new(Some(self.cards()), Some(read_filter.clone()));
+        self.imp().read_filter.replace(Some(read_filter));
+
+        let models = ListStore::new::<gio::ListModel>();
+        models.append(&filter_model);
+        models.append(&self.placeholders());
+        let flatten_model = FlattenListModel::new(Some(models));
+
         // Wrap model with selection and pass it to the list view
-        let selection_model = NoSelection::new(Some(self.cards()));
+        let selection_model = NoSelection::new(Some(flatten_model));
         self.imp().cards_list.set_model(Some(&selection_model));
+
+        self.imp().cards_list.connect_activate(clone!(
+            #[weak(rename_to = feed_page)]
+            self,
+            move |list_view, position| {
+                let story_object = list_view
+                    .model()
+                    .and_then(|model| model.item(position))
+                    .and_downcast::<StoryObject>()
+                    .expect("The item has to be an `StoryObject`.");
+
+                if story_object.placeholder() {
+                    return;
+                }
+
+                // stories without a link, like Ask HN posts, only have comments to open
+                let open_in_browser = feed_page.settings().string("link-opening-mode") == "browser";
+                if open_in_browser && !story_object.url().is_empty() {
+                    feed_page.mark_read(&story_object);
+                    open_uri(&feed_page, &story_object.url());
+                } else {
+                    feed_page.open_comments(&story_object);
+                }
+            }
+        ));
+
+        self.connect_hide_read_notify(|feed_page| feed_page.refilter());
+    }
+
+    fn setup_settings(&self) {
+        let settings = crate::settings::settings();
+
+        settings.bind("hide-read-stories", self, "hide-read").build();
+
+        for key in CardLayout::KEYS {
+            settings.connect_changed(
+                Some(key),
+                clone!(
+                    #[weak(rename_to = feed_page)]
+                    self,
+                    move |_, _| feed_page.setup_factory()
+                ),
+            );
+        }
+
+        self.imp()
+            .settings
+            .set(settings)
+            .expect("`settings` should not be set before calling `setup_settings`.");
+    }
+
+    fn setup_read_history(&self) {
+        let max_age_days = self.settings().uint("history-max-age") as i64;
+
+        let read_history = ReadHistory::load(max_age_days);
+        read_history.save();
+        self.imp().read_history.replace(read_history);
+
+        let hidden_stories = HiddenStories::load(max_age_days);
+        hidden_stories.save();
+        self.imp().hidden_stories.replace(hidden_stories);
+
+        self.imp().bookmarks.replace(Bookmarks::load());
+    }
+
+    fn setup_mute_filters(&self) {
+        let mute_filters = MuteFilters::load(self.settings());
+        self.imp().mute_matcher.replace(Some(mute_filters.matcher()));
+    }
+
+    // shows what the cards are loaded from, with a way back to the feed for sites and searches
+    pub fn show_source(&self, source: &FeedSource) {
+        self.set_title(&source.title());
+
+        let banner = self.imp().source_banner.get();
+        match source {
+            FeedSource::Feed(_) => banner.set_revealed(false),
+            FeedSource::Site(domain) => {
+                banner.set_title(&fill(&gettext("Stories from {}"), &[&markup_escape_text(domain)]));
+                banner.set_revealed(true);
+            }
+            FeedSource::Search(query) => {
+                banner.set_title(&fill(&gettext("Results for “{}”"), &[&markup_escape_text(query)]));
+                banner.set_revealed(true);
+            }
+        }
+    }
+
+    fn placeholders(&self) -> ListStore {
+        self.imp()
+            .placeholders
+            .borrow()
+            .clone()
+            .expect("Could not get current placeholders.")
+    }
+
+    // clears the feed for a fresh batch of stories,
+    // with a skeleton card standing in for each of them until it arrives
+    pub fn begin_stories(&self, count: usize) {
+        self.imp().refreshed.replace(None);
+        self.imp().stories.borrow_mut().clear();
+        self.cards().remove_all();
+        self.filtered().remove_all();
+        if let Some(prefetcher) = self.imp().prefetcher.get() {
+            prefetcher.reset();
+        }
+        // the comments seen so far are read once per batch, the story page keeps them up to date on disk
+        self.imp().seen_comments.replace(SeenComments::load());
+
+        let placeholders = self.placeholders();
+        let skeletons: Vec<StoryObject> = (0..count).map(|_| StoryObject::placeholder()).collect();
+        placeholders.splice(0, placeholders.n_items(), &skeletons);
+        self.update_filtered_banner();
+    }
+
+    // starts taking in a batch fetched in the background, which is held back until all of it is in,
+    // so the feed doesn't change under the user while it arrives
+    pub fn begin_refresh(&self) {
+        self.imp().refreshed.replace(Some(vec![]));
+    }
+
+    // adds the next story of the batch, which come in rank order, in place of one of the skeleton cards
+    pub fn add_story(&self, story_data: StoryData) {
+        if let Some(refreshed) = self.imp().refreshed.borrow_mut().as_mut() {
+            refreshed.push(story_data);
+            return;
+        }
+
+        let story_object = self.new_story_object(story_data);
+        // the stories arrive in the order of the feed
+        story_object.set_rank(self.imp().stories.borrow().len() as u32 + 1);
+        self.imp().stories.borrow_mut().push(story_object.clone());
+        self.append_unless_muted(&story_object);
+
+        let placeholders = self.placeholders();
+        if placeholders.n_items() > 0 {
+            placeholders.remove(placeholders.n_items() - 1);
+        }
+        self.update_filtered_banner();
+    }
+
+    // drops the skeleton cards of stories that failed to load once the whole batch is in
+    pub fn finish_stories(&self) {
+        self.placeholders().remove_all();
+
+        if let Some(offset) = self.imp().pending_scroll_offset.take() {
+            // the list only knows its full height once the cards have been laid out
+            glib::idle_add_local_once(clone!(
+                #[weak(rename_to = feed_page)]
+                self,
+                move || feed_page.imp().scrolled_window.vadjustment().set_value(offset)
+            ));
+        }
+    }
+
+    // puts the stories of a background refresh in place of the ones listed, reusing the cards of the stories
+    // still in the feed so the list stays where the user has scrolled to.
+    // a refresh that is missing stories is dropped, rather than leaving gaps in the feed
+    pub fn finish_refresh(&self, complete: bool) {
+        let Some(refreshed) = self.imp().refreshed.take() else {
+            return;
+        };
+        self.placeholders().remove_all();
+        if !complete {
+            return;
+        }
+
+        self.imp().seen_comments.replace(SeenComments::load());
+        let mut listed: HashMap<u32, StoryObject> = self
+            .imp()
+            .stories
+            .take()
+            .into_iter()
+            .map(|story_object| (story_object.id(), story_object))
+            .collect();
+
+        let stories: Vec<StoryObject> = refreshed
+            .into_iter()
+            .enumerate()
+            .map(|(index, story_data)| {
+                let story_object = match listed.remove(&story_data.id) {
+                    Some(story_object) => {
+                        story_object.update(story_data);
+                        story_object.set_new_comments_count(
+                            self.imp()
+                                .seen_comments
+                                .borrow()
+                                .new_count(story_object.id(), story_object.comments_count()),
+                        );
+                        story_object
+                    }
+                    None => self.new_story_object(story_data),
+                };
+                story_object.set_rank(index as u32 + 1);
+                story_object
+            })
+            .collect();
+        self.imp().stories.replace(stories);
+
+        self.sort_stories();
+    }
+
+    // a story as it is first listed, with what the user has done with it so far
+    fn new_story_object(&self, story_data: StoryData) -> StoryObject {
+        let story_object = StoryObject::new(story_data);
+        story_object.set_read(self.imp().read_history.borrow().is_read(story_object.id()));
+        story_object.set_bookmarked(self.imp().bookmarks.borrow().is_bookmarked(story_object.id()));
+        story_object.set_selected(story_object.id() == self.imp().selected_id.get());
+        story_object.set_new_comments_count(
+            self.imp()
+                .seen_comments
+                .borrow()
+                .new_count(story_object.id(), story_object.comments_count()),
+        );
+        story_object
+    }
+
+    pub fn scroll_offset(&self) -> f64 {
+        self.imp().scrolled_window.vadjustment().value()
+    }
+
+    // scrolls back to where the feed was left in the previous session once its stories arrive
+    pub fn restore_scroll_offset(&self, offset: f64) {
+        self.imp().pending_scroll_offset.set(Some(offset));
+    }
+
+    // muted and hidden stories never make it into `cards`, they go into `filtered` instead
+    fn append_unless_muted(&self, story_object: &StoryObject) {
+        if self.is_muted(story_object) {
+            self.filtered().append(story_object);
+        } else {
+            self.cards().append(story_object);
+        }
+    }
+
+    fn is_muted(&self, story_object: &StoryObject) -> bool {
+        self.imp().mute_matcher.borrow().as_ref().is_some_and(|mute_matcher| {
+            mute_matcher.is_muted(
+                &story_object.title(),
+                &story_object.domain(),
+                &story_object.author(),
+                story_object.score_count(),
+            )
+        }) || self.imp().hidden_stories.borrow().is_hidden(story_object.id())
+    }
+
+    // re-reads the mute filters and sorts every fetched story into `cards` or `filtered` again
+    pub fn reload_mute_filters(&self) {
+        self.setup_mute_filters();
+        self.sort_stories();
+    }
+
+    // brings `cards` and `filtered` in line with `stories`, changing only the cards that moved
+    // so the list keeps its scroll position on the ones that stay
+    fn sort_stories(&self) {
+        let (filtered, cards): (Vec<StoryObject>, Vec<StoryObject>) = self
+            .imp()
+            .stories
+            .borrow()
+            .iter()
+            .cloned()
+            .partition(|story_object| self.is_muted(story_object));
+
+        sync_store(&self.cards(), &cards);
+        sync_store(&self.filtered(), &filtered);
+        self.update_filtered_banner();
+    }
+
+    // highlights the card of the story open next to the feed.
+    // the id is kept around so the highlight survives the feed being fetched again
+    pub fn select_story(&self, story_id: u32) {
+        self.imp().selected_id.set(story_id);
+        for story_object in self.imp().stories.borrow().iter() {
+            story_object.set_selected(story_object.id() == story_id);
+        }
+    }
+
+    fn update_filtered_banner(&self) {
+        let filtered_count = self.filtered().n_items();
+        let banner = self.imp().filtered_banner.get();
+
+        banner.set_title(&fill(
+            &ngettext("{} story filtered", "{} stories filtered", filtered_count),
+            &[&format_number(filtered_count)],
+        ));
+        banner.set_revealed(filtered_count > 0);
+    }
+
+    // lets the user peek at the stories the mute filters have hidden
+    fn show_filtered(&self) {
+        let filtered_list = ListView::builder()
+            .model(&NoSelection::new(Some(self.filtered())))
+            .factory(&story_card_factory(CardLayout::load(self.settings())))
+            .valign(gtk::Align::Start)
+            .build();
+
+        let toolbar_view = adw::ToolbarView::new();
+        toolbar_view.add_top_bar(&adw::HeaderBar::new());
+        toolbar_view.set_content(Some(
+            &ScrolledWindow::builder()
+                .has_frame(false)
+                .vexpand(true)
+                .child(&filtered_list)
+                .build(),
+        ));
+
+        let dialog = adw::Dialog::builder()
+            .title(gettext("Filtered Stories"))
+            .content_width(360)
+            .content_height(600)
+            .child(&toolbar_view)
+            .build();
+        dialog.present(Some(self));
     }
 
-    pub fn setup_cards(&self, story_data_vec: Vec<StoryData>) {
-        // this may be a candidate for using rayon?
-        // https://rust-lang-nursery.github.io/rust-cookbook/concurrency/parallel.html
-        for story_data in story_data_vec {
-            let story_object = StoryObject::new(story_data);
-            self.cards().append(&story_object);
+    // brings the time labels of all stories up to date, including the ones the filters hide
+    pub fn refresh_times(&self) {
+        for story_object in self.imp().stories.borrow().iter() {
+            story_object.refresh_time();
         }
     }
 
+    // called once the comments of a story have been shown, so its card stops announcing new ones
+    pub fn mark_comments_seen(&self, story_id: u32) {
+        for story_object in self.imp().stories.borrow().iter() {
+            if story_object.id() == story_id {
+                story_object.set_new_comments_count(0);
+            }
+        }
+    }
+
+    // the story whose id an action was activated with
+    fn story_for_parameter(&self, parameter: Option<&glib::Variant>) -> Option<StoryObject> {
+        self.story_by_id(parameter?.get::<u32>()?)
+    }
+
+    fn story_by_id(&self, story_id: u32) -> Option<StoryObject> {
+        self.imp()
+            .stories
+            .borrow()
+            .iter()
+            .find(|story_object| story_object.id() == story_id)
+            .cloned()
+    }
+
+    // the card holding the keyboard focus, while the focus is in the feed
+    fn focused_card(&self) -> Option<StoryCard> {
+        self.imp()
+            .cards_list
+            .focus_child()?
+            .first_child()
+            .and_downcast::<StoryCard>()
+    }
+
+    // the story the keyboard shortcuts act on: the one whose card has the focus,
+    // or else the one open next to the feed
+    pub fn current_story(&self) -> Option<StoryObject> {
+        self.focused_card()
+            .and_then(|story_card| story_card.story_object())
+            .filter(|story_object| !story_object.placeholder())
+            .or_else(|| self.story_by_id(self.imp().selected_id.get()))
+    }
+
+    // moves the keyboard focus to the card after or before the focused one,
+    // or to the card at the top of the visible part of the feed when none has the focus yet
+    pub fn focus_next_story(&self, forward: bool) {
+        let cards_list = self.imp().cards_list.get();
+        let Some(model) = cards_list.model() else {
+            return;
+        };
+        let position_of = |story_object: &StoryObject| {
+            (0..model.n_items()).find(|position| {
+                model.item(*position).as_ref() == Some(story_object.upcast_ref::<glib::Object>())
+            })
+        };
+
+        let position = match self.focused_card().and_then(|story_card| story_card.story_object()) {
+            Some(story_object) => match (position_of(&story_object), forward) {
+                (Some(position), true) => position + 1,
+                (Some(position), false) if position > 0 => position - 1,
+                _ => return,
+            },
+            None => self
+                .top_visible_story()
+                .and_then(|story_object| position_of(&story_object))
+                .unwrap_or(0),
+        };
+
+        let is_story = model
+            .item(position)
+            .and_downcast::<StoryObject>()
+            .is_some_and(|story_object| !story_object.placeholder());
+        if is_story {
+            cards_list.scroll_to(position, ListScrollFlags::FOCUS, None);
+        }
+    }
+
+    pub fn open_comments(&self, story_object: &StoryObject) {
+        self.mark_read(story_object);
+        self.select_story(story_object.id());
+        self.emit_by_name::<()>("story-activated", &[&story_object.id()]);
+    }
+
+    // opens the article a story links to, or its discussion on the website for stories without a link
+    pub fn open_link(&self, story_object: &StoryObject) {
+        self.mark_read(story_object);
+        if story_object.url().is_empty() {
+            open_uri(self, &website_item_url(story_object.id()));
+        } else {
+            open_uri(self, &story_object.url());
+        }
+    }
+
+    pub fn toggle_bookmark(&self, story_object: &StoryObject) {
+        let mut bookmarks = self.imp().bookmarks.borrow_mut();
+        story_object.set_bookmarked(bookmarks.toggle(story_object.id()));
+        bookmarks.save();
+    }
+
+    // takes a single story out of the feed for good, keeping it with the filtered ones
+    fn hide_story(&self, story_object: &StoryObject) {
+        let mut hidden_stories = self.imp().hidden_stories.borrow_mut();
+        hidden_stories.hide(story_object.id());
+        hidden_stories.save();
+        drop(hidden_stories);
+
+        if let Some(position) = self.cards().find(story_object) {
+            self.cards().remove(position);
+            self.filtered().append(story_object);
+            self.update_filtered_banner();
+        }
+    }
+
+    fn mark_read(&self, story_object: &StoryObject) {
+        story_object.set_read(true);
+
+        let mut read_history = self.imp().read_history.borrow_mut();
+        read_history.mark_read(story_object.id());
+        read_history.save();
+    }
+
+    // marks every story ranked above the card at the top of the visible part of the feed as read
+    fn mark_above_read(&self) {
+        let Some(top_position) = self.top_visible_position() else {
+            return;
+        };
+
+        let mut read_history = self.imp().read_history.borrow_mut();
+        for position in 0..top_position {
+            if let Some(story_object) = self.cards().item(position).and_downcast::<StoryObject>() {
+                story_object.set_read(true);
+                read_history.mark_read(story_object.id());
+            }
+        }
+        read_history.save();
+        drop(read_history);
+
+        self.refilter();
+    }
+
+    // saves every story the feed currently lists for offline reading
+    fn save_feed_offline(&self) {
+        let cards = self.cards();
+        let story_ids: Vec<u32> = (0..cards.n_items())
+            .filter_map(|position| cards.item(position).and_downcast::<StoryObject>())
+            .map(|story_object| story_object.id())
+            .collect();
+
+        if !story_ids.is_empty() {
+            let _ = self.activate_action("app.save-offline", Some(&story_ids.to_variant()));
+        }
+    }
+
+    // the position in `cards` of the story shown at the top edge of the scrolled window
+    fn top_visible_position(&self) -> Option<u32> {
+        self.cards().find(&self.top_visible_story()?)
+    }
+
+    fn top_visible_story(&self) -> Option<StoryObject> {
+        let scrolled_window = self.imp().scrolled_window.get();
+        let picked = scrolled_window.pick(
+            scrolled_window.width() as f64 / 2.0,
+            1.0,
+            PickFlags::DEFAULT,
+        )?;
+
+        // the pick can land in the margin around a card, which belongs to the list row holding it
+        let story_card = picked
+            .ancestor(StoryCard::static_type())
+            .or_else(|| picked.first_child())
+            .and_downcast::<StoryCard>()?;
+
+        story_card.story_object()
+    }
+
+    fn refilter(&self) {
+        if let Some(read_filter) = self.imp().read_filter.borrow().as_ref() {
+            read_filter.changed(FilterChange::Different);
+        }
+    }
+
+    // the cards are made anew by a fresh factory whenever their layout changes in the preferences
     fn setup_factory(&self) {
-        // Create a new factory
-        let factory = SignalListItemFactory::new();
-
-        // Create an empty `StoryCard` during setup
-        factory.connect_setup(move |_, list_item| {
-            // Create `StoryCard`
-            let story_card = StoryCard::new();
-            list_item
-                .downcast_ref::<ListItem>()
-                .expect("Needs to be ListItem")
-                .set_child(Some(&story_card));
-        });
-
-        // Tell factory how to bind `StoryCard` to a `StoryObject`
-        factory.connect_bind(move |_, list_item| {
-            // Get `StoryObject` from `ListItem`
-            let story_object = list_item
-                .downcast_ref::<ListItem>()
-                .expect("Needs to be ListItem")
-                .item()
-                .and_downcast::<StoryObject>()
-                .expect("The item has to be an `StoryObject`.");
-
-            // Get `StoryCard` from `ListItem`
-            let story_card = list_item
-                .downcast_ref::<ListItem>()
-                .expect("Needs to be ListItem")
-                .child()
-                .and_downcast::<StoryCard>()
-                .expect("The child has to be a `StoryCard`.");
-
-            story_card.bind(&story_object);
-        });
-
-        // Tell factory how to unbind `StoryCard` from `StoryObject`
-        factory.connect_unbind(move |_, list_item| {
-            // Get `TaskRow` from `ListItem`
-            let story_card = list_item
-                .downcast_ref::<ListItem>()
-                .expect("Needs to be ListItem")
-                .child()
-                .and_downcast::<StoryCard>()
-                .expect("The child has to be a `StoryCard`.");
-
-            story_card.unbind();
-        });
+        let factory = story_card_factory(CardLayout::load(self.settings()));
+
+        // the comments of the stories scrolled into view are fetched ahead of time
+        factory.connect_bind(clone!(
+            #[weak(rename_to = feed_page)]
+            self,
+            move |_, list_item| {
+                if let (Some(story_object), Some(prefetcher)) =
+                    (list_item_story(list_item), feed_page.imp().prefetcher.get())
+                {
+                    if !story_object.placeholder() && story_object.comments_count() > 0 {
+                        prefetcher.request(story_object.id());
+                    }
+                }
+            }
+        ));
+        factory.connect_unbind(clone!(
+            #[weak(rename_to = feed_page)]
+            self,
+            move |_, list_item| {
+                if let (Some(story_object), Some(prefetcher)) =
+                    (list_item_story(list_item), feed_page.imp().prefetcher.get())
+                {
+                    prefetcher.cancel(story_object.id());
+                }
+            }
+        ));
 
         // Set the factory of the list view
         self.imp().cards_list.set_factory(Some(&factory));
     }
+
+    pub fn set_prefetcher(&self, prefetcher: CommentPrefetcher) {
+        if self.imp().prefetcher.set(prefetcher).is_err() {
+            println!("The comment prefetcher can only be set once");
+        }
+    }
+}
+
+// changes the items of `store` into `items` in place. the list view showing it keeps track of the
+// cards on screen as items come and go around them, but would jump back to the top if they were all replaced
+fn sync_store(store: &ListStore, items: &[StoryObject]) {
+    for (position, item) in items.iter().enumerate() {
+        let position = position as u32;
+        if store.item(position).as_ref() == Some(item.upcast_ref::<glib::Object>()) {
+            continue;
+        }
+        // the items before `position` are in place already, so one listed already can only be further down
+        if let Some(old_position) = store.find(item) {
+            store.remove(old_position);
+        }
+        store.insert(position, item);
+    }
+
+    let count = items.len() as u32;
+    while store.n_items() > count {
+        store.remove(count);
+    }
+}
+
+fn list_item_story(list_item: &glib::Object) -> Option<StoryObject> {
+    list_item
+        .downcast_ref::<ListItem>()
+        .and_then(|list_item| list_item.item())
+        .and_downcast::<StoryObject>()
+}
+
+// creates the factory that turns `StoryObject`s into `StoryCard`s laid out as given,
+// shared by the feed and the list of filtered stories
+fn story_card_factory(layout: CardLayout) -> SignalListItemFactory {
+    // Create a new factory
+    let factory = SignalListItemFactory::new();
+
+    // Create an empty `StoryCard` during setup
+    factory.connect_setup(move |_, list_item| {
+        // Create `StoryCard`
+        let story_card = StoryCard::new();
+        let list_item = list_item.downcast_ref::<ListItem>().expect("Needs to be ListItem");
+        // the card takes the keyboard focus instead of the row around it, so it is what screen readers
+        // announce and it gets the keys for its menu and link
+        list_item.set_focusable(false);
+        list_item.set_child(Some(&story_card));
+    });
+
+    // Tell factory how to bind `StoryCard` to a `StoryObject`
+    factory.connect_bind(move |_, list_item| {
+        // Get `StoryObject` from `ListItem`
+        let story_object = list_item
+            .downcast_ref::<ListItem>()
+            .expect("Needs to be ListItem")
+            .item()
+            .and_downcast::<StoryObject>()
+            .expect("The item has to be an `StoryObject`.");
+
+        // Get `StoryCard` from `ListItem`
+        let story_card = list_item
+            .downcast_ref::<ListItem>()
+            .expect("Needs to be ListItem")
+            .child()
+            .and_downcast::<StoryCard>()
+            .expect("The child has to be a `StoryCard`.");
+
+        story_card.bind(&story_object, &layout);
+    });
+
+    // Tell factory how to unbind `StoryCard` from `StoryObject`
+    factory.connect_unbind(move |_, list_item| {
+        // Get `TaskRow` from `ListItem`
+        let story_card = list_item
+            .downcast_ref::<ListItem>()
+            .expect("Needs to be ListItem")
+            .child()
+            .and_downcast::<StoryCard>()
+            .expect("The child has to be a `StoryCard`.");
+
+        story_card.unbind();
+    });
+
+    factory
 }
 
 mod imp {
+    use std::sync::OnceLock;
+
+    use glib::subclass::{prelude::DerivedObjectProperties, Signal};
+    use glib::{Properties, VariantTy};
+
     use super::*;
 
     // ANCHOR: struct_and_subclass
     // Object holding the state
-    #[derive(CompositeTemplate, Default)]
+    #[derive(CompositeTemplate, Properties, Default)]
     #[template(file = "src/ui/feed_page.blp")]
+    #[properties(wrapper_type = super::FeedPage)]
     pub struct FeedPage {
+        #[template_child]
+        pub scrolled_window: TemplateChild<ScrolledWindow>,
         #[template_child]
         pub cards_list: TemplateChild<ListView>,
+        #[template_child]
+        pub source_banner: TemplateChild<adw::Banner>,
+        #[template_child]
+        pub filtered_banner: TemplateChild<adw::Banner>,
         pub cards: RefCell<Option<ListStore>>,
+        pub placeholders: RefCell<Option<ListStore>>,
+        // every story fetched for the feed in rank order, whether muted or not
+        pub stories: RefCell<Vec<StoryObject>>,
+        pub filtered: RefCell<Option<ListStore>>,
+        pub mute_matcher: RefCell<Option<MuteMatcher>>,
+        pub settings: OnceCell<gio::Settings>,
+        pub read_filter: RefCell<Option<CustomFilter>>,
+        pub read_history: RefCell<ReadHistory>,
+        pub bookmarks: RefCell<Bookmarks>,
+        pub hidden_stories: RefCell<HiddenStories>,
+        pub seen_comments: RefCell<SeenComments>,
+        pub prefetcher: OnceCell<CommentPrefetcher>,
+        pub selected_id: Cell<u32>,
+        pub pending_scroll_offset: Cell<Option<f64>>,
+        // the stories of a background refresh that are still coming in
+        pub refreshed: RefCell<Option<Vec<StoryData>>>,
+        #[property(get, set)]
+        pub hide_read: Cell<bool>,
     }
 
     // The central trait for subclassing a GObject
@@ -141,6 +801,40 @@ mod imp {
 
         fn class_init(klass: &mut Self::Class) {
             klass.bind_template();
+
+            klass.install_property_action("feed.hide-read", "hide-read");
+            klass.install_action("feed.mark-above-read", None, |feed_page, _, _| {
+                feed_page.mark_above_read();
+            });
+            klass.install_action("feed.save-offline", None, |feed_page, _, _| {
+                feed_page.save_feed_offline();
+            });
+            // the mute filters are a page of the preferences, which the window opens
+            klass.install_action("feed.show-mute-filters", None, |feed_page, _, _| {
+                let _ = feed_page.activate_action("win.show-mute-filters", None);
+            });
+            // the actions on single stories are activated by their cards with the id of the story
+            klass.install_action("feed.open-comments", Some(VariantTy::UINT32), |feed_page, _, parameter| {
+                if let Some(story_object) = feed_page.story_for_parameter(parameter) {
+                    feed_page.open_comments(&story_object);
+                }
+            });
+            klass.install_action("feed.bookmark", Some(VariantTy::UINT32), |feed_page, _, parameter| {
+                if let Some(story_object) = feed_page.story_for_parameter(parameter) {
+                    feed_page.toggle_bookmark(&story_object);
+                }
+            });
+            klass.install_action("feed.hide", Some(VariantTy::UINT32), |feed_page, _, parameter| {
+                if let Some(story_object) = feed_page.story_for_parameter(parameter) {
+                    feed_page.hide_story(&story_object);
+                }
+            });
+            klass.install_action("feed.mark-read", Some(VariantTy::UINT32), |feed_page, _, parameter| {
+                if let Some(story_object) = feed_page.story_for_parameter(parameter) {
+                    feed_page.mark_read(&story_object);
+                    feed_page.refilter();
+                }
+            });
         }
 
         fn instance_init(obj: &InitializingObject<Self>) {
@@ -151,6 +845,7 @@ mod imp {
 
     // ANCHOR: constructed
     // Trait shared by all GObjects
+    #[glib::derived_properties]
     impl ObjectImpl for FeedPage {
         fn constructed(&self) {
             // Call "constructed" on parent
@@ -158,14 +853,22 @@ mod imp {
 
             // Setup
             let obj = self.obj();
+            obj.setup_settings();
+            obj.show_source(&FeedSource::Feed(Feed::from_name(&obj.settings().string("default-feed"))));
+            obj.setup_read_history();
+            obj.setup_mute_filters();
             obj.setup_model_and_view();
             obj.setup_factory();
         }
 
-        //fn signals() -> &'static [glib::subclass::Signal] {
-        //    static SIGNALS: OnceLock<Vec<Signal>> = OnceLock::new();
-        //    SIGNALS.get_or_init(|| vec![Signal::builder("fetch-cards").build()])
-        //}
+        fn signals() -> &'static [Signal] {
+            static SIGNALS: OnceLock<Vec<Signal>> = OnceLock::new();
+            SIGNALS.get_or_init(|| {
+                vec![Signal::builder("story-activated")
+                    .param_types([u32::static_type()])
+                    .build()]
+            })
+        }
     }
     // ANCHOR_END: constructed
 
@@ -0,0 +1,28 @@
+use std::collections::HashMap;
+
+use gtk::gio::{self, prelude::ApplicationExt};
+use gtk::glib::{self, ToVariant};
+
+use crate::application::APP_ID;
+
+// asks feedbackd, which phones running Phosh use for haptics, to give a short vibration.
+// GTK has no haptics of its own, so on desktops without feedbackd nothing happens
+pub fn haptic_cue() {
+    let Some(connection) = gio::Application::default().and_then(|app| app.dbus_connection()) else {
+        return;
+    };
+
+    let hints: HashMap<&str, glib::Variant> = HashMap::new();
+    connection.call(
+        Some("org.sigxcpu.Feedback"),
+        "/org/sigxcpu/Feedback",
+        "org.sigxcpu.Feedback",
+        "TriggerFeedback",
+        Some(&(APP_ID, "button-pressed", hints, -1i32).to_variant()),
+        None,
+        gio::DBusCallFlags::NO_AUTO_START,
+        -1,
+        None::<&gio::Cancellable>,
+        |_| {},
+    );
+}
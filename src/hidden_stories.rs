@@ -0,0 +1,31 @@
+use crate::storage::TimestampedIds;
+
+const HIDDEN_STORIES_FILE: &str = "hidden_stories.json";
+
+// the stories the user has hidden from the feed one by one, with when each was hidden
+pub struct HiddenStories(TimestampedIds);
+
+impl Default for HiddenStories {
+    fn default() -> Self {
+        HiddenStories(TimestampedIds::new(HIDDEN_STORIES_FILE, "hidden"))
+    }
+}
+
+impl HiddenStories {
+    // stories hidden more than `max_age_days` ago are forgotten, by which point they have long dropped out of the feeds
+    pub fn load(max_age_days: i64) -> Self {
+        HiddenStories(TimestampedIds::load(HIDDEN_STORIES_FILE, "hidden", Some(max_age_days)))
+    }
+
+    pub fn save(&self) {
+        self.0.save();
+    }
+
+    pub fn is_hidden(&self, id: u32) -> bool {
+        self.0.contains(id)
+    }
+
+    pub fn hide(&mut self, id: u32) {
+        self.0.insert(id);
+    }
+}
@@ -0,0 +1,253 @@
+use gtk::glib::markup_escape_text;
+use gtk::pango;
+
+// entities are short, an ampersand without a semicolon soon after it is just an ampersand
+const MAX_ENTITY_LENGTH: usize = 10;
+
+// what the HTML is turned into
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Output {
+    Markup,
+    // Pango itself doesn't know about links, only labels do, so the markup is checked without them
+    MarkupWithoutLinks,
+    Text,
+}
+
+// turns the HTML the API serves comment text and user profiles in into Pango markup for a label.
+// Hacker News only uses paragraphs, italics, links and code blocks, anything else is dropped
+// and only its text is kept. should the result still not parse, the label gets the plain text,
+// since a label given markup it can't parse shows nothing at all
+pub fn html_to_markup(html: &str) -> String {
+    if pango::parse_markup(&convert(html, Output::MarkupWithoutLinks), '\0').is_ok() {
+        convert(html, Output::Markup)
+    } else {
+        markup_escape_text(&convert(html, Output::Text)).to_string()
+    }
+}
+
+// replaces character references like &amp;, &#x27; and &#8212; with the characters they stand for.
+// unknown ones are left as they are
+pub fn decode_entities(text: &str) -> String {
+    let mut decoded = String::with_capacity(text.len());
+    let mut rest = text;
+
+    while let Some(start) = rest.find('&') {
+        decoded.push_str(&rest[..start]);
+        rest = &rest[start..];
+
+        let entity = rest[1..]
+            .find(';')
+            .filter(|end| *end <= MAX_ENTITY_LENGTH)
+            .and_then(|end| decode_entity(&rest[1..1 + end]).map(|character| (character, end)));
+        match entity {
+            Some((character, end)) => {
+                decoded.push(character);
+                rest = &rest[end + 2..];
+            }
+            None => {
+                decoded.push('&');
+                rest = &rest[1..];
+            }
+        }
+    }
+
+    decoded.push_str(rest);
+    decoded
+}
+
+fn decode_entity(name: &str) -> Option<char> {
+    if let Some(number) = name.strip_prefix('#') {
+        let code = match number.strip_prefix(['x', 'X']) {
+            Some(hex) => u32::from_str_radix(hex, 16).ok()?,
+            None => number.parse().ok()?,
+        };
+        // a NUL would cut the text short
+        return char::from_u32(code).filter(|character| *character != '\0');
+    }
+
+    let character = match name {
+        "amp" => '&',
+        "lt" => '<',
+        "gt" => '>',
+        "quot" => '"',
+        "apos" => '\'',
+        "nbsp" => '\u{a0}',
+        "mdash" => '—',
+        "ndash" => '–',
+        "hellip" => '…',
+        "lsquo" => '‘',
+        "rsquo" => '’',
+        "ldquo" => '“',
+        "rdquo" => '”',
+        "copy" => '©',
+        "times" => '×',
+        _ => return None,
+    };
+    Some(character)
+}
+
+fn convert(html: &str, output: Output) -> String {
+    let mut converted = String::with_capacity(html.len());
+    // the tags left open, with the markup that opened them
+    let mut open: Vec<(&'static str, String)> = vec![];
+    let mut rest = html;
+
+    while let Some(start) = rest.find('<') {
+        push_text(&mut converted, &rest[..start], output);
+        rest = &rest[start..];
+
+        // a "<" that doesn't start a tag is part of the text
+        let starts_tag = rest[1..].starts_with(|character: char| character.is_ascii_alphabetic() || character == '/');
+        match rest.find('>') {
+            Some(end) if starts_tag => {
+                push_tag(&mut converted, &mut open, &rest[1..end], output);
+                rest = &rest[end + 1..];
+            }
+            _ => {
+                push_text(&mut converted, "<", output);
+                rest = &rest[1..];
+            }
+        }
+    }
+
+    push_text(&mut converted, rest, output);
+    for (name, _) in open.iter().rev() {
+        converted.push_str(&format!("</{}>", name));
+    }
+    converted
+}
+
+fn push_text(converted: &mut String, text: &str, output: Output) {
+    let text = decode_entities(text);
+    if output == Output::Text {
+        converted.push_str(&text);
+    } else {
+        converted.push_str(&markup_escape_text(&text));
+    }
+}
+
+fn push_tag(converted: &mut String, open: &mut Vec<(&'static str, String)>, tag: &str, output: Output) {
+    let closing = tag.starts_with('/');
+    let name = tag
+        .trim_start_matches('/')
+        .split(|character: char| character.is_whitespace() || character == '/')
+        .next()
+        .unwrap_or_default()
+        .to_ascii_lowercase();
+
+    match name.as_str() {
+        "p" if !closing => converted.push_str("\n\n"),
+        "br" => converted.push('\n'),
+        _ if output == Output::Text => {}
+        _ => {
+            let Some(markup_name) = markup_tag(&name, tag, output) else {
+                return;
+            };
+            if closing {
+                close_tag(converted, open, markup_name);
+            } else {
+                let opening = match markup_name {
+                    "a" => format!("<a href=\"{}\">", markup_escape_text(&link_target(tag).unwrap_or_default())),
+                    _ => format!("<{}>", markup_name),
+                };
+                converted.push_str(&opening);
+                open.push((markup_name, opening));
+            }
+        }
+    }
+}
+
+// the Pango tag an HTML tag becomes, if any
+fn markup_tag(name: &str, tag: &str, output: Output) -> Option<&'static str> {
+    match name {
+        "i" | "em" => Some("i"),
+        "b" | "strong" => Some("b"),
+        "code" => Some("tt"),
+        // a link that goes nowhere a browser could open is left as text
+        "a" if output == Output::Markup && (tag.starts_with('/') || link_target(tag).is_some()) => Some("a"),
+        _ => None,
+    }
+}
+
+fn link_target(tag: &str) -> Option<String> {
+    let start = tag.find("href=\"")? + "href=\"".len();
+    let end = start + tag[start..].find('"')?;
+    let target = decode_entities(&tag[start..end]);
+    (target.starts_with("https://") || target.starts_with("http://")).then_some(target)
+}
+
+// closes a tag, and whatever was opened inside of it and left open, which is opened again after it
+fn close_tag(converted: &mut String, open: &mut Vec<(&'static str, String)>, name: &str) {
+    let Some(position) = open.iter().rposition(|(open_name, _)| *open_name == name) else {
+        return;
+    };
+
+    let reopened = open.split_off(position + 1);
+    for (open_name, _) in reopened.iter().rev() {
+        converted.push_str(&format!("</{}>", open_name));
+    }
+    converted.push_str(&format!("</{}>", name));
+    open.pop();
+
+    for (_, opening) in &reopened {
+        converted.push_str(opening);
+    }
+    open.extend(reopened);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn named_and_numeric_entities_are_decoded() {
+        assert_eq!(decode_entities("Tom &amp; Jerry&#x27;s &quot;show&quot;"), "Tom & Jerry's \"show\"");
+        assert_eq!(decode_entities("a &lt;b&gt; c&#8212;d&#X2F;"), "a <b> c—d/");
+    }
+
+    #[test]
+    fn unknown_and_unterminated_entities_are_kept() {
+        assert_eq!(decode_entities("&bogus; & AT&T"), "&bogus; & AT&T");
+        assert_eq!(decode_entities("fish &amp chips"), "fish &amp chips");
+        assert_eq!(decode_entities("&#0; &#xZZ;"), "&#0; &#xZZ;");
+    }
+
+    #[test]
+    fn comment_html_becomes_markup() {
+        assert_eq!(
+            html_to_markup("First<p>Second with <i>emphasis</i> &amp; <code>x &lt; y</code>"),
+            "First\n\nSecond with <i>emphasis</i> &amp; <tt>x &lt; y</tt>"
+        );
+    }
+
+    #[test]
+    fn links_keep_their_target() {
+        assert_eq!(
+            html_to_markup(r#"see <a href="https:&#x2F;&#x2F;example.com&#x2F;?a=1&amp;b=2" rel="nofollow">this</a>"#),
+            r#"see <a href="https://example.com/?a=1&amp;b=2">this</a>"#
+        );
+    }
+
+    #[test]
+    fn links_to_anything_but_the_web_are_text() {
+        assert_eq!(html_to_markup(r#"<a href="javascript:alert(1)">click</a>"#), "click");
+    }
+
+    #[test]
+    fn unknown_tags_are_dropped() {
+        assert_eq!(html_to_markup("<span class=\"x\">hi</span> <blink>there</blink>"), "hi there");
+    }
+
+    #[test]
+    fn unbalanced_tags_are_balanced() {
+        assert_eq!(html_to_markup("<i>open"), "<i>open</i>");
+        assert_eq!(html_to_markup("stray</i> close"), "stray close");
+        assert_eq!(html_to_markup("<i>a<b>b</i>c</b>"), "<i>a<b>b</b></i><b>c</b>");
+    }
+
+    #[test]
+    fn a_lone_angle_bracket_is_text() {
+        assert_eq!(html_to_markup("1 < 2"), "1 &lt; 2");
+        assert_eq!(html_to_markup("1 < 2 and 3 > 1"), "1 &lt; 2 and 3 &gt; 1");
+    }
+}
@@ -0,0 +1,349 @@
+use std::collections::{BTreeMap, HashMap};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU32, AtomicU64, Ordering};
+use std::sync::{Mutex, OnceLock};
+
+use chrono::Utc;
+use reqwest::header::{HeaderMap, CACHE_CONTROL, ETAG, LAST_MODIFIED};
+use serde::{Deserialize, Serialize};
+
+use crate::storage::cache_dir;
+use crate::transform::runtime;
+
+// how long a response counts as fresh when the server doesn't say, in seconds.
+// Firebase marks everything no-cache, which would have every scroll revalidate the same items
+const DEFAULT_MAX_AGE: i64 = 60;
+// Hacker News closes stories to new comments and votes after two weeks,
+// so items older than that are not going to change anymore
+const IMMUTABLE_AFTER: i64 = 14 * 24 * 60 * 60;
+// how many responses are kept in memory on top of the disk cache
+const MEMORY_ENTRIES: usize = 2000;
+// how many responses are written to disk between checks of the size of the cache
+const WRITES_BETWEEN_TRIMS: u32 = 100;
+
+// the maximum size of the disk cache in bytes, set from the preferences. 0 keeps responses in memory only
+static MAX_DISK_SIZE: AtomicU64 = AtomicU64::new(50 * 1024 * 1024);
+static WRITES_SINCE_TRIM: AtomicU32 = AtomicU32::new(0);
+
+pub fn set_cache_size(megabytes: u64) {
+    MAX_DISK_SIZE.store(megabytes * 1024 * 1024, Ordering::Relaxed);
+}
+
+// a response kept together with what is needed to tell whether it is still fresh,
+// and to ask the server whether it has changed once it isn't
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct CacheEntry {
+    pub body: String,
+    pub etag: Option<String>,
+    pub last_modified: Option<String>,
+    // unix timestamp of when the response was fetched or last revalidated
+    pub fetched_at: i64,
+    pub max_age: i64,
+    pub immutable: bool,
+}
+
+impl CacheEntry {
+    pub fn new(body: String, headers: &HeaderMap) -> Self {
+        let header = |name| {
+            headers
+                .get(name)
+                .and_then(|value| value.to_str().ok())
+                .map(|value| value.to_string())
+        };
+
+        CacheEntry {
+            immutable: is_old_item(&body),
+            body,
+            etag: header(ETAG),
+            last_modified: header(LAST_MODIFIED),
+            fetched_at: Utc::now().timestamp(),
+            max_age: max_age(headers),
+        }
+    }
+
+    pub fn is_fresh(&self) -> bool {
+        self.immutable || Utc::now().timestamp() - self.fetched_at < self.max_age
+    }
+
+    // the response once the server has confirmed it is unchanged, fresh again for as long as the server now says
+    pub fn revalidated(mut self, headers: &HeaderMap) -> Self {
+        self.fetched_at = Utc::now().timestamp();
+        self.max_age = max_age(headers);
+        self
+    }
+
+    // whether the server can be asked if the response has changed, rather than sending it again
+    pub fn can_revalidate(&self) -> bool {
+        self.etag.is_some() || self.last_modified.is_some()
+    }
+}
+
+// keeps API responses in memory and in $XDG_CACHE_HOME, keyed by their URL
+pub struct HttpCache {
+    memory: Mutex<MemoryCache>,
+}
+
+pub fn http_cache() -> &'static HttpCache {
+    static CACHE: OnceLock<HttpCache> = OnceLock::new();
+    CACHE.get_or_init(|| HttpCache {
+        memory: Mutex::new(MemoryCache::new(MEMORY_ENTRIES)),
+    })
+}
+
+impl HttpCache {
+    pub async fn get(&self, url: &str) -> Option<CacheEntry> {
+        if let Some(entry) = self.memory().get(url) {
+            return Some(entry);
+        }
+
+        let bytes = tokio::fs::read(entry_path(url)).await.ok()?;
+        let entry: CacheEntry = serde_json::from_slice(&bytes).ok()?;
+        self.memory().insert(url, entry.clone());
+        Some(entry)
+    }
+
+    // the response is written to disk in the background, the request it came with doesn't wait on it
+    pub fn store(&self, url: &str, entry: CacheEntry) {
+        self.memory().insert(url, entry.clone());
+
+        if MAX_DISK_SIZE.load(Ordering::Relaxed) == 0 {
+            return;
+        }
+
+        let url = url.to_string();
+        runtime().spawn_blocking(move || {
+            let result = serde_json::to_vec(&entry)
+                .map_err(|e| e.to_string())
+                .and_then(|bytes| fs::write(entry_path(&url), bytes).map_err(|e| e.to_string()));
+            if let Err(e) = result {
+                println!("Could not cache {}: {}", url, e);
+            }
+        });
+
+        if WRITES_SINCE_TRIM.fetch_add(1, Ordering::Relaxed) + 1 >= WRITES_BETWEEN_TRIMS {
+            self.trim();
+        }
+    }
+
+    // shrinks the disk cache to the size chosen in the preferences.
+    // that means going over every file, which is left to a blocking thread of the tokio runtime
+    pub fn trim(&self) {
+        WRITES_SINCE_TRIM.store(0, Ordering::Relaxed);
+        runtime().spawn_blocking(trim_disk);
+    }
+
+    fn memory(&self) -> std::sync::MutexGuard<'_, MemoryCache> {
+        self.memory.lock().expect("The cache lock should not be poisoned.")
+    }
+}
+
+// the responses used most recently, dropping the one used longest ago once it is full
+struct MemoryCache {
+    capacity: usize,
+    // every entry with the tick it was last used at
+    entries: HashMap<String, (CacheEntry, u64)>,
+    // the URLs of the entries by the tick they were last used at, so the oldest comes first
+    recency: BTreeMap<u64, String>,
+    tick: u64,
+}
+
+impl MemoryCache {
+    fn new(capacity: usize) -> Self {
+        MemoryCache {
+            capacity,
+            entries: HashMap::new(),
+            recency: BTreeMap::new(),
+            tick: 0,
+        }
+    }
+
+    fn get(&mut self, url: &str) -> Option<CacheEntry> {
+        let tick = self.next_tick();
+        let (entry, last_used) = self.entries.get_mut(url)?;
+        self.recency.remove(last_used);
+        self.recency.insert(tick, url.to_string());
+        *last_used = tick;
+        Some(entry.clone())
+    }
+
+    fn insert(&mut self, url: &str, entry: CacheEntry) {
+        let tick = self.next_tick();
+        if let Some((_, last_used)) = self.entries.insert(url.to_string(), (entry, tick)) {
+            self.recency.remove(&last_used);
+        }
+        self.recency.insert(tick, url.to_string());
+
+        while self.entries.len() > self.capacity {
+            let Some((_, oldest)) = self.recency.pop_first() else {
+                break;
+            };
+            self.entries.remove(&oldest);
+        }
+    }
+
+    fn next_tick(&mut self) -> u64 {
+        self.tick += 1;
+        self.tick
+    }
+}
+
+// deletes the responses fetched longest ago until the disk cache fits in the size chosen in the preferences
+fn trim_disk() {
+    let max_size = MAX_DISK_SIZE.load(Ordering::Relaxed);
+
+    let Ok(dir_entries) = fs::read_dir(entries_dir()) else {
+        return;
+    };
+    let mut files: Vec<(PathBuf, u64, std::time::SystemTime)> = dir_entries
+        .flatten()
+        .filter_map(|dir_entry| {
+            let metadata = dir_entry.metadata().ok()?;
+            Some((dir_entry.path(), metadata.len(), metadata.modified().ok()?))
+        })
+        .collect();
+
+    let mut total_size: u64 = files.iter().map(|(_, size, _)| size).sum();
+    files.sort_by_key(|(_, _, modified)| *modified);
+
+    for (path, size, _) in files {
+        if total_size <= max_size {
+            break;
+        }
+        if fs::remove_file(&path).is_ok() {
+            total_size -= size;
+        }
+    }
+}
+
+// created the first time it is needed
+fn entries_dir() -> &'static Path {
+    static ENTRIES_DIR: OnceLock<PathBuf> = OnceLock::new();
+    ENTRIES_DIR.get_or_init(|| {
+        let dir = cache_dir().join("http");
+
+        if let Err(e) = fs::create_dir_all(&dir) {
+            println!("Could not create cache directory {}: {}", dir.display(), e);
+        }
+
+        dir
+    })
+}
+
+// URLs can be longer than a file name may be, so entries are stored under a hash of theirs
+fn entry_path(url: &str) -> PathBuf {
+    entries_dir().join(format!("{:016x}.json", fnv1a(url.as_bytes())))
+}
+
+// a hash that stays the same across builds, unlike the one from the standard library
+pub fn fnv1a(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(0xcbf29ce484222325, |hash, byte| {
+        (hash ^ *byte as u64).wrapping_mul(0x100000001b3)
+    })
+}
+
+fn max_age(headers: &HeaderMap) -> i64 {
+    headers
+        .get(CACHE_CONTROL)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| {
+            value
+                .split(',')
+                .find_map(|directive| directive.trim().strip_prefix("max-age="))
+                .and_then(|seconds| seconds.parse::<i64>().ok())
+        })
+        .filter(|seconds| *seconds > 0)
+        .unwrap_or(DEFAULT_MAX_AGE)
+}
+
+// only items carry a `time`, so lists, users and searches are never taken to be immutable
+fn is_old_item(body: &str) -> bool {
+    #[derive(Deserialize)]
+    struct Timestamped {
+        time: Option<i64>,
+    }
+
+    serde_json::from_str::<Timestamped>(body)
+        .ok()
+        .and_then(|timestamped| timestamped.time)
+        .is_some_and(|time| Utc::now().timestamp() - time > IMMUTABLE_AFTER)
+}
+
+#[cfg(test)]
+mod tests {
+    use reqwest::header::HeaderValue;
+
+    use super::*;
+
+    fn entry(body: &str, fetched_at: i64, max_age: i64) -> CacheEntry {
+        CacheEntry {
+            body: body.to_string(),
+            etag: None,
+            last_modified: None,
+            fetched_at,
+            max_age,
+            immutable: false,
+        }
+    }
+
+    fn cache_control(value: &'static str) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        headers.insert(CACHE_CONTROL, HeaderValue::from_static(value));
+        headers
+    }
+
+    #[test]
+    fn max_age_is_read_from_cache_control() {
+        assert_eq!(max_age(&cache_control("public, max-age=300")), 300);
+        assert_eq!(max_age(&cache_control("max-age=30, must-revalidate")), 30);
+    }
+
+    #[test]
+    fn max_age_falls_back_to_the_default() {
+        assert_eq!(max_age(&HeaderMap::new()), DEFAULT_MAX_AGE);
+        assert_eq!(max_age(&cache_control("no-cache")), DEFAULT_MAX_AGE);
+        assert_eq!(max_age(&cache_control("max-age=0")), DEFAULT_MAX_AGE);
+        assert_eq!(max_age(&cache_control("max-age=soon")), DEFAULT_MAX_AGE);
+    }
+
+    #[test]
+    fn entries_are_fresh_for_their_max_age() {
+        let now = Utc::now().timestamp();
+        assert!(entry("{}", now - 10, 60).is_fresh());
+        assert!(!entry("{}", now - 61, 60).is_fresh());
+
+        let mut old = entry("{}", now - 1000, 60);
+        old.immutable = true;
+        assert!(old.is_fresh());
+    }
+
+    #[test]
+    fn fnv1a_is_stable() {
+        assert_eq!(fnv1a(b""), 0xcbf29ce484222325);
+        assert_eq!(fnv1a(b"a"), 0xaf63dc4c8601ec8c);
+        assert_ne!(fnv1a(b"item/1.json"), fnv1a(b"item/2.json"));
+    }
+
+    #[test]
+    fn only_items_past_two_weeks_are_old() {
+        let now = Utc::now().timestamp();
+        assert!(is_old_item(&format!(r#"{{"id": 1, "time": {}}}"#, now - IMMUTABLE_AFTER - 60)));
+        assert!(!is_old_item(&format!(r#"{{"id": 1, "time": {}}}"#, now - 60)));
+        assert!(!is_old_item("[1, 2, 3]"));
+        assert!(!is_old_item(r#"{"id": "pg", "karma": 1}"#));
+    }
+
+    #[test]
+    fn the_entry_used_longest_ago_is_dropped() {
+        let mut memory = MemoryCache::new(2);
+        memory.insert("a", entry("a", 0, 60));
+        memory.insert("b", entry("b", 0, 60));
+        assert!(memory.get("a").is_some());
+
+        memory.insert("c", entry("c", 0, 60));
+        assert!(memory.get("b").is_none());
+        assert!(memory.get("a").is_some());
+        assert!(memory.get("c").is_some());
+        assert_eq!(memory.recency.len(), 2);
+    }
+}
@@ -0,0 +1,195 @@
+use std::ffi::CStr;
+use std::sync::OnceLock;
+
+use gettextrs::{bind_textdomain_codeset, bindtextdomain, setlocale, textdomain, LocaleCategory};
+
+// the name the translations are installed under, in $LOCALEDIR/<language>/LC_MESSAGES/<name>.mo
+pub const GETTEXT_PACKAGE: &str = "gtk-glider-clone";
+// set by `make` to where it installs the translations, the system's locale directory otherwise
+const LOCALEDIR: &str = match option_env!("LOCALEDIR") {
+    Some(dir) => dir,
+    None => "/usr/share/locale",
+};
+
+// how the locale numbers are formatted for groups digits, read on start
+static NUMBER_GROUPING: OnceLock<NumberGrouping> = OnceLock::new();
+
+// the thousands separator of a locale, and the sizes of the groups of digits it separates,
+// starting from the rightmost. the last size repeats, so [3] groups by thousands
+// and [3, 2] the way India does, like 12,34,567, unless the grouping ends with `Rest`
+#[derive(Default)]
+struct NumberGrouping {
+    separator: String,
+    sizes: Vec<GroupSize>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum GroupSize {
+    Digits(usize),
+    // the digits further left all make up one group, however many there are
+    Rest,
+}
+
+// picks up the language and number format from the environment ($LANG, $LC_ALL and friends),
+// and points gettext at the app's translations. the templates are translated with the same domain,
+// so this has to run before any widget is built
+pub fn init() {
+    let grouping = setlocale(LocaleCategory::LcAll, "")
+        .and_then(|_| setlocale(LocaleCategory::LcNumeric, ""))
+        .map(|_| locale_number_grouping())
+        .unwrap_or_default();
+    let _ = NUMBER_GROUPING.set(grouping);
+
+    let result = bindtextdomain(GETTEXT_PACKAGE, LOCALEDIR)
+        .and_then(|_| bind_textdomain_codeset(GETTEXT_PACKAGE, "UTF-8"))
+        .and_then(|_| textdomain(GETTEXT_PACKAGE));
+    if let Err(e) = result {
+        println!("Could not set up translations: {}", e);
+    }
+}
+
+// fills the placeholders of a translated string: "{}" takes the next value and "{1}" the value at that index,
+// so translators can put the values in the order their language needs, like "{1} of {0}"
+pub fn fill(template: &str, values: &[&str]) -> String {
+    let mut filled = String::with_capacity(template.len());
+    let mut next = 0;
+    let mut rest = template;
+
+    while let Some(start) = rest.find('{') {
+        filled.push_str(&rest[..start]);
+        rest = &rest[start..];
+
+        let placeholder = rest[1..]
+            .find('}')
+            .map(|end| &rest[1..1 + end])
+            .filter(|index| index.chars().all(|character| character.is_ascii_digit()));
+        match placeholder {
+            Some(index) => {
+                let index = if index.is_empty() {
+                    next += 1;
+                    next - 1
+                } else {
+                    index.parse().unwrap_or(usize::MAX)
+                };
+                filled.push_str(values.get(index).copied().unwrap_or_default());
+                rest = &rest[placeholder.map_or(0, str::len) + 2..];
+            }
+            None => {
+                filled.push('{');
+                rest = &rest[1..];
+            }
+        }
+    }
+
+    filled.push_str(rest);
+    filled
+}
+
+// writes out a count with the digit grouping of the user's locale, like 1,234, 1.234 or 12,34,567
+pub fn format_number(number: u32) -> String {
+    match NUMBER_GROUPING.get() {
+        Some(grouping) => group_digits(&number.to_string(), grouping),
+        None => number.to_string(),
+    }
+}
+
+fn group_digits(digits: &str, grouping: &NumberGrouping) -> String {
+    if grouping.separator.is_empty() || grouping.sizes.is_empty() {
+        return digits.to_string();
+    }
+
+    // the groups are cut off from the right, then put back together from the left
+    let mut groups: Vec<&str> = vec![];
+    let mut end = digits.len();
+    let mut sizes = grouping.sizes.iter();
+    let mut size = 0;
+    while end > 0 {
+        size = match sizes.next() {
+            Some(GroupSize::Digits(digits)) => *digits,
+            Some(GroupSize::Rest) => end,
+            None => size,
+        };
+        let start = end.saturating_sub(size);
+        groups.push(&digits[start..end]);
+        end = start;
+    }
+
+    groups.reverse();
+    groups.join(&grouping.separator)
+}
+
+// reads the separator and group sizes of the numeric locale set by `init` from the C library
+fn locale_number_grouping() -> NumberGrouping {
+    // SAFETY: localeconv points to data that stays valid until the locale changes or it is called again,
+    // neither of which can happen while the strings are copied here, since it only runs from `init` on start
+    let (separator, sizes) = unsafe {
+        let conventions = libc::localeconv();
+        if conventions.is_null() || (*conventions).thousands_sep.is_null() || (*conventions).grouping.is_null() {
+            return NumberGrouping::default();
+        }
+        (
+            CStr::from_ptr((*conventions).thousands_sep).to_string_lossy().into_owned(),
+            CStr::from_ptr((*conventions).grouping).to_bytes().to_vec(),
+        )
+    };
+
+    // CHAR_MAX ends the grouping, leaving the digits further left as they are
+    let mut group_sizes = vec![];
+    for size in sizes {
+        if size >= libc::c_char::MAX as u8 {
+            group_sizes.push(GroupSize::Rest);
+            break;
+        }
+        group_sizes.push(GroupSize::Digits(usize::from(size)));
+    }
+
+    NumberGrouping {
+        separator,
+        sizes: group_sizes,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use GroupSize::{Digits, Rest};
+
+    fn grouping(separator: &str, sizes: &[GroupSize]) -> NumberGrouping {
+        NumberGrouping {
+            separator: separator.to_string(),
+            sizes: sizes.to_vec(),
+        }
+    }
+
+    #[test]
+    fn placeholders_are_filled_in_order() {
+        assert_eq!(fill("{} sent, {} retried, {} failed", &["3", "2", "1"]), "3 sent, 2 retried, 1 failed");
+        assert_eq!(fill("{} and {}", &["one"]), "one and ");
+    }
+
+    #[test]
+    fn numbered_placeholders_can_be_reordered() {
+        assert_eq!(fill("{2} failed, {0} sent, {1} retried", &["3", "2", "1"]), "1 failed, 3 sent, 2 retried");
+        assert_eq!(fill("{1} von {0}", &["5", "2"]), "2 von 5");
+    }
+
+    #[test]
+    fn braces_that_are_no_placeholders_are_kept() {
+        assert_eq!(fill("{x} {", &["a"]), "{x} {");
+        assert_eq!(fill("“{}”", &["rust"]), "“rust”");
+    }
+
+    #[test]
+    fn digits_are_grouped_the_locale_way() {
+        assert_eq!(group_digits("1234567", &grouping(",", &[Digits(3)])), "1,234,567");
+        assert_eq!(group_digits("1234567", &grouping(".", &[Digits(3)])), "1.234.567");
+        assert_eq!(group_digits("1234567", &grouping(",", &[Digits(3), Digits(2)])), "12,34,567");
+        assert_eq!(group_digits("123", &grouping(",", &[Digits(3)])), "123");
+        assert_eq!(group_digits("1234", &grouping("", &[Digits(3)])), "1234");
+        assert_eq!(group_digits("1234", &grouping(",", &[])), "1234");
+        assert_eq!(group_digits("1234567", &grouping(",", &[Digits(3), Rest])), "1234,567");
+        assert_eq!(group_digits("123456789", &grouping(",", &[Digits(3), Digits(2), Rest])), "1234,56,789");
+        assert_eq!(group_digits("1234567", &grouping(",", &[Rest])), "1234567");
+    }
+}
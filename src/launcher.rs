@@ -0,0 +1,12 @@
+use gtk::{gio, prelude::*, UriLauncher};
+
+// opens a link in the user's web browser, going through the OpenURI portal when sandboxed
+pub fn open_uri(widget: &impl IsA<gtk::Widget>, uri: &str) {
+    let window = widget.root().and_downcast::<gtk::Window>();
+
+    UriLauncher::new(uri).launch(window.as_ref(), None::<&gio::Cancellable>, |result| {
+        if let Err(e) = result {
+            println!("Could not open link: {}", e);
+        }
+    });
+}
@@ -5,13 +5,45 @@ pub mod window;
 pub mod feed_page;
 pub mod story_page;
 pub mod story_card;
+pub mod card_layout;
+pub mod favicon;
+pub mod thumbnail;
 pub mod story_object;
+pub mod storage;
+pub mod read_history;
+pub mod comment_object;
+pub mod comment_row;
+pub mod seen_comments;
+pub mod mute_filters;
+pub mod mute_filters_page;
+pub mod settings;
+pub mod preferences_dialog;
+pub mod launcher;
+pub mod swipe_action;
+pub mod feedback;
+pub mod bookmarks;
+pub mod hidden_stories;
+pub mod share;
+pub mod user_page;
+pub mod deep_link;
+pub mod session;
+pub mod http_cache;
+pub mod data_usage;
+pub mod data_saver;
+pub mod prefetch;
+pub mod offline_store;
+pub mod offline_page;
+pub mod relative_time;
+pub mod i18n;
+pub mod html;
 
 use adw::{prelude::*, Application};
 use application::App;
 use gtk::gio;
 
 fn main() {
+    i18n::init();
+
     gio::resources_register_include!("compiled.gresource").expect("Failed to register resources.");
 
     let application: Application = App::new();
@@ -0,0 +1,253 @@
+use std::fs;
+
+use gtk::gio::{
+    self,
+    prelude::{SettingsExt, SettingsExtManual},
+};
+use regex::{Regex, RegexBuilder};
+use serde::Deserialize;
+
+use crate::storage::{data_dir, load_json};
+
+// where the filters were kept before they moved into the settings
+const LEGACY_MUTE_FILTERS_FILE: &str = "mute_filters.json";
+
+// which of the user-defined mute lists an entry belongs to
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum MuteList {
+    Keywords,
+    Domains,
+    Authors,
+}
+
+impl MuteList {
+    pub const ALL: [MuteList; 3] = [MuteList::Keywords, MuteList::Domains, MuteList::Authors];
+
+    // the settings key the list is stored under
+    pub fn key(&self) -> &'static str {
+        match self {
+            MuteList::Keywords => "muted-keywords",
+            MuteList::Domains => "muted-domains",
+            MuteList::Authors => "muted-authors",
+        }
+    }
+}
+
+// user-defined rules for hiding stories from the feed.
+// keywords are matched case-insensitively anywhere in the title,
+// unless they are wrapped in slashes like `/rust|go/`, in which case they are used as a regular expression
+#[derive(Deserialize, Default, Clone)]
+#[serde(default)]
+pub struct MuteFilters {
+    pub keywords: Vec<String>,
+    pub domains: Vec<String>,
+    pub authors: Vec<String>,
+    pub min_score: u32,
+}
+
+// the mute filters prepared for matching against a batch of stories
+pub struct MuteMatcher {
+    title_patterns: Vec<Regex>,
+    domains: Vec<String>,
+    authors: Vec<String>,
+    min_score: u32,
+}
+
+impl MuteFilters {
+    pub fn load(settings: &gio::Settings) -> Self {
+        migrate_legacy_file(settings);
+
+        let list = |mute_list: MuteList| -> Vec<String> {
+            settings.strv(mute_list.key()).iter().map(|entry| entry.to_string()).collect()
+        };
+
+        MuteFilters {
+            keywords: list(MuteList::Keywords),
+            domains: list(MuteList::Domains),
+            authors: list(MuteList::Authors),
+            min_score: settings.uint("min-score"),
+        }
+    }
+
+    pub fn save(&self, settings: &gio::Settings) {
+        for mute_list in MuteList::ALL {
+            settings
+                .set_strv(mute_list.key(), self.list(mute_list))
+                .expect("The mute lists need to be writable.");
+        }
+        settings
+            .set_uint("min-score", self.min_score)
+            .expect("The minimum score needs to be writable.");
+    }
+
+    pub fn list(&self, mute_list: MuteList) -> &[String] {
+        match mute_list {
+            MuteList::Keywords => &self.keywords,
+            MuteList::Domains => &self.domains,
+            MuteList::Authors => &self.authors,
+        }
+    }
+
+    pub fn list_mut(&mut self, mute_list: MuteList) -> &mut Vec<String> {
+        match mute_list {
+            MuteList::Keywords => &mut self.keywords,
+            MuteList::Domains => &mut self.domains,
+            MuteList::Authors => &mut self.authors,
+        }
+    }
+
+    pub fn matcher(&self) -> MuteMatcher {
+        let title_patterns: Vec<Regex> = self
+            .keywords
+            .iter()
+            .filter_map(|keyword| {
+                let pattern = match keyword.strip_prefix('/').and_then(|k| k.strip_suffix('/')) {
+                    Some(regex) => regex.to_string(),
+                    None => regex::escape(keyword),
+                };
+
+                RegexBuilder::new(&pattern)
+                    .case_insensitive(true)
+                    .build()
+                    .map_err(|e| println!("Ignoring invalid mute pattern {}: {}", keyword, e))
+                    .ok()
+            })
+            .collect();
+
+        MuteMatcher {
+            title_patterns,
+            domains: self.domains.iter().map(|domain| normalize_domain(domain)).collect(),
+            authors: self.authors.iter().map(|author| author.to_lowercase()).collect(),
+            min_score: self.min_score,
+        }
+    }
+}
+
+impl MuteMatcher {
+    pub fn is_muted(&self, title: &str, domain: &str, author: &str, score: u32) -> bool {
+        let domain = normalize_domain(domain);
+        let author = author.to_lowercase();
+
+        score < self.min_score
+            || self.title_patterns.iter().any(|pattern| pattern.is_match(title))
+            || self.authors.iter().any(|muted| *muted == author)
+            // muting a domain also mutes its subdomains
+            || self.domains.iter().any(|muted| {
+                domain == *muted || domain.ends_with(format!(".{}", muted).as_str())
+            })
+    }
+}
+
+// moves the filters saved to a JSON file by earlier versions into the settings, adding them to
+// whatever is there already. the file is removed afterwards, so this only happens once
+fn migrate_legacy_file(settings: &gio::Settings) {
+    let path = data_dir().join(LEGACY_MUTE_FILTERS_FILE);
+    if !path.exists() {
+        return;
+    }
+
+    let legacy: MuteFilters = load_json(LEGACY_MUTE_FILTERS_FILE);
+    let list = |mute_list: MuteList| -> Vec<String> {
+        let mut entries: Vec<String> = settings.strv(mute_list.key()).iter().map(|entry| entry.to_string()).collect();
+        for entry in legacy.list(mute_list) {
+            if !entries.contains(entry) {
+                entries.push(entry.clone());
+            }
+        }
+        entries
+    };
+    let merged = MuteFilters {
+        keywords: list(MuteList::Keywords),
+        domains: list(MuteList::Domains),
+        authors: list(MuteList::Authors),
+        min_score: settings.uint("min-score").max(legacy.min_score),
+    };
+    merged.save(settings);
+
+    if let Err(e) = fs::remove_file(&path) {
+        println!("Could not remove {}: {}", path.display(), e);
+    }
+}
+
+fn normalize_domain(domain: &str) -> String {
+    let domain = domain.trim().to_lowercase();
+    domain.strip_prefix("www.").unwrap_or(&domain).to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn is_muted(mute_filters: MuteFilters, title: &str, domain: &str, author: &str, score: u32) -> bool {
+        mute_filters.matcher().is_muted(title, domain, author, score)
+    }
+
+    fn keywords(keywords: &[&str]) -> MuteFilters {
+        MuteFilters {
+            keywords: keywords.iter().map(|keyword| keyword.to_string()).collect(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn keywords_match_anywhere_in_the_title_ignoring_case() {
+        assert!(is_muted(keywords(&["crypto"]), "The Crypto Winter", "", "", 100));
+        assert!(is_muted(keywords(&["crypto"]), "Cryptocurrency news", "", "", 100));
+        assert!(!is_muted(keywords(&["crypto"]), "Rust 2.0 released", "", "", 100));
+    }
+
+    #[test]
+    fn keywords_are_taken_literally() {
+        assert!(is_muted(keywords(&["c++"]), "Why C++ is hard", "", "", 100));
+        assert!(!is_muted(keywords(&["a.b"]), "axb", "", "", 100));
+    }
+
+    #[test]
+    fn keywords_in_slashes_are_regular_expressions() {
+        let mute_filters = keywords(&["/^show hn:.*(ai|llm)/"]);
+        assert!(is_muted(mute_filters.clone(), "Show HN: An LLM for cats", "", "", 100));
+        assert!(!is_muted(mute_filters.clone(), "Ask HN: An LLM for cats?", "", "", 100));
+        assert!(!is_muted(mute_filters, "Show HN: A bike", "", "", 100));
+    }
+
+    #[test]
+    fn invalid_regular_expressions_are_ignored() {
+        let mute_filters = keywords(&["/(unclosed/", "spam"]);
+        assert!(!is_muted(mute_filters.clone(), "(unclosed", "", "", 100));
+        assert!(is_muted(mute_filters, "More spam", "", "", 100));
+    }
+
+    #[test]
+    fn domains_match_themselves_and_their_subdomains() {
+        let mute_filters = MuteFilters {
+            domains: vec!["WWW.Example.com ".to_string()],
+            ..Default::default()
+        };
+        assert!(is_muted(mute_filters.clone(), "", "example.com", "", 100));
+        assert!(is_muted(mute_filters.clone(), "", "www.example.com", "", 100));
+        assert!(is_muted(mute_filters.clone(), "", "blog.example.com", "", 100));
+        assert!(!is_muted(mute_filters.clone(), "", "notexample.com", "", 100));
+        assert!(!is_muted(mute_filters, "", "example.com.au", "", 100));
+    }
+
+    #[test]
+    fn authors_match_ignoring_case() {
+        let mute_filters = MuteFilters {
+            authors: vec!["Spammer".to_string()],
+            ..Default::default()
+        };
+        assert!(is_muted(mute_filters.clone(), "", "", "spammer", 100));
+        assert!(!is_muted(mute_filters, "", "", "spammer2", 100));
+    }
+
+    #[test]
+    fn stories_below_the_minimum_score_are_muted() {
+        let mute_filters = MuteFilters {
+            min_score: 10,
+            ..Default::default()
+        };
+        assert!(is_muted(mute_filters.clone(), "", "", "", 9));
+        assert!(!is_muted(mute_filters.clone(), "", "", "", 10));
+        assert!(!is_muted(MuteFilters::default(), "", "", "", 0));
+    }
+}
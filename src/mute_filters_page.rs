@@ -0,0 +1,232 @@
+use adw::prelude::{ActionRowExt, EditableExt, PreferencesGroupExt};
+use adw::subclass::prelude::PreferencesPageImpl;
+use glib::subclass::{
+    types::{ObjectSubclass, ObjectSubclassExt},
+    InitializingObject,
+};
+use gettextrs::gettext;
+use gtk::glib::{self, clone, closure_local, Object};
+use gtk::prelude::{ButtonExt, ObjectExt};
+use gtk::subclass::prelude::ObjectSubclassIsExt;
+use gtk::subclass::widget::WidgetClassExt;
+use gtk::subclass::{
+    prelude::{ObjectImpl, ObjectImplExt},
+    widget::{CompositeTemplateClass, CompositeTemplateInitializingExt, WidgetImpl},
+};
+use gtk::{Align, Button, CompositeTemplate, TemplateChild};
+use std::cell::RefCell;
+
+use crate::mute_filters::{MuteFilters, MuteList};
+use crate::settings::settings;
+
+glib::wrapper! {
+    pub struct MuteFiltersPage(ObjectSubclass<imp::MuteFiltersPage>)
+        @extends adw::PreferencesPage, gtk::Widget,
+        @implements gtk::Accessible, gtk::Buildable,
+                    gtk::ConstraintTarget;
+}
+
+impl Default for MuteFiltersPage {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl MuteFiltersPage {
+    pub fn new() -> Self {
+        Object::builder().build()
+    }
+
+    // calls `f` every time the user adds or removes a filter
+    pub fn connect_filters_changed<F: Fn(&Self) + 'static>(&self, f: F) -> glib::SignalHandlerId {
+        self.connect_closure(
+            "filters-changed",
+            false,
+            closure_local!(move |mute_filters_page: MuteFiltersPage| f(&mute_filters_page)),
+        )
+    }
+
+    fn group(&self, mute_list: MuteList) -> adw::PreferencesGroup {
+        match mute_list {
+            MuteList::Keywords => self.imp().keywords_group.get(),
+            MuteList::Domains => self.imp().domains_group.get(),
+            MuteList::Authors => self.imp().authors_group.get(),
+        }
+    }
+
+    fn entry(&self, mute_list: MuteList) -> adw::EntryRow {
+        match mute_list {
+            MuteList::Keywords => self.imp().keywords_entry.get(),
+            MuteList::Domains => self.imp().domains_entry.get(),
+            MuteList::Authors => self.imp().authors_entry.get(),
+        }
+    }
+
+    fn setup_lists(&self) {
+        let mute_filters = MuteFilters::load(&settings());
+
+        for mute_list in MuteList::ALL {
+            for entry in mute_filters.list(mute_list) {
+                self.add_entry_row(mute_list, entry);
+            }
+
+            self.entry(mute_list).connect_apply(clone!(
+                #[weak(rename_to = mute_filters_page)]
+                self,
+                move |entry_row| {
+                    let text = entry_row.text().trim().to_string();
+                    if text.is_empty() {
+                        return;
+                    }
+
+                    mute_filters_page.update_filters(|mute_filters| {
+                        mute_filters.list_mut(mute_list).push(text.clone())
+                    });
+                    mute_filters_page.add_entry_row(mute_list, &text);
+                    entry_row.set_text("");
+                }
+            ));
+        }
+
+        let min_score_row = self.imp().min_score_row.get();
+        min_score_row.set_value(mute_filters.min_score as f64);
+        min_score_row.connect_value_notify(clone!(
+            #[weak(rename_to = mute_filters_page)]
+            self,
+            move |min_score_row| {
+                mute_filters_page.update_filters(|mute_filters| {
+                    mute_filters.min_score = min_score_row.value() as u32
+                });
+            }
+        ));
+
+        self.imp().mute_filters.replace(mute_filters);
+    }
+
+    // shows an existing filter entry in its group, with a button to remove it again
+    fn add_entry_row(&self, mute_list: MuteList, entry: &str) {
+        let row = adw::ActionRow::builder().title(entry).use_markup(false).build();
+        let remove_button = Button::builder()
+            .icon_name("user-trash-symbolic")
+            .tooltip_text(gettext("Remove"))
+            .valign(Align::Center)
+            .css_classes(["flat"])
+            .build();
+
+        remove_button.connect_clicked(clone!(
+            #[weak(rename_to = mute_filters_page)]
+            self,
+            #[weak]
+            row,
+            move |_| mute_filters_page.remove_entry_row(mute_list, &row)
+        ));
+
+        row.add_suffix(&remove_button);
+        self.group(mute_list).add(&row);
+        self.imp().entry_rows.borrow_mut().push((mute_list, row));
+    }
+
+    // removes the entry shown by a row, going by its position in the list,
+    // so only that one goes when the same entry has been added twice
+    fn remove_entry_row(&self, mute_list: MuteList, row: &adw::ActionRow) {
+        let mut entry_rows = self.imp().entry_rows.borrow_mut();
+        let Some(position) = entry_rows.iter().position(|(_, entry_row)| entry_row == row) else {
+            return;
+        };
+        let index = entry_rows[..position]
+            .iter()
+            .filter(|(entry_list, _)| *entry_list == mute_list)
+            .count();
+        entry_rows.remove(position);
+        drop(entry_rows);
+
+        self.update_filters(|mute_filters| {
+            let entries = mute_filters.list_mut(mute_list);
+            if index < entries.len() {
+                entries.remove(index);
+            }
+        });
+        self.group(mute_list).remove(row);
+    }
+
+    // changes the filters, writes them to the settings and lets the feed know
+    fn update_filters<F: FnOnce(&mut MuteFilters)>(&self, f: F) {
+        let mut mute_filters = self.imp().mute_filters.borrow_mut();
+        f(&mut mute_filters);
+        mute_filters.save(&settings());
+        drop(mute_filters);
+
+        self.emit_by_name::<()>("filters-changed", &[]);
+    }
+}
+
+mod imp {
+    use std::sync::OnceLock;
+
+    use glib::subclass::Signal;
+
+    use super::*;
+
+    // Object holding the state
+    #[derive(CompositeTemplate, Default)]
+    #[template(file = "src/ui/mute_filters_page.blp")]
+    pub struct MuteFiltersPage {
+        #[template_child]
+        pub keywords_group: TemplateChild<adw::PreferencesGroup>,
+        #[template_child]
+        pub keywords_entry: TemplateChild<adw::EntryRow>,
+        #[template_child]
+        pub domains_group: TemplateChild<adw::PreferencesGroup>,
+        #[template_child]
+        pub domains_entry: TemplateChild<adw::EntryRow>,
+        #[template_child]
+        pub authors_group: TemplateChild<adw::PreferencesGroup>,
+        #[template_child]
+        pub authors_entry: TemplateChild<adw::EntryRow>,
+        #[template_child]
+        pub min_score_row: TemplateChild<adw::SpinRow>,
+        pub mute_filters: RefCell<MuteFilters>,
+        // the rows of the entries in all lists, in the order of the entries within each list
+        pub entry_rows: RefCell<Vec<(MuteList, adw::ActionRow)>>,
+    }
+
+    // The central trait for subclassing a GObject
+    #[glib::object_subclass]
+    impl ObjectSubclass for MuteFiltersPage {
+        // `NAME` needs to match `class` attribute of template
+        const NAME: &'static str = "MuteFiltersPage";
+        type Type = super::MuteFiltersPage;
+        type ParentType = adw::PreferencesPage;
+
+        fn class_init(klass: &mut Self::Class) {
+            klass.bind_template();
+        }
+
+        fn instance_init(obj: &InitializingObject<Self>) {
+            obj.init_template();
+        }
+    }
+
+    // Trait shared by all GObjects
+    impl ObjectImpl for MuteFiltersPage {
+        fn constructed(&self) {
+            // Call "constructed" on parent
+            self.parent_constructed();
+
+            // Setup
+            let obj = self.obj();
+            obj.setup_lists();
+        }
+
+        fn signals() -> &'static [Signal] {
+            static SIGNALS: OnceLock<Vec<Signal>> = OnceLock::new();
+            SIGNALS.get_or_init(|| vec![Signal::builder("filters-changed").build()])
+        }
+    }
+
+    // Trait shared by all widgets
+    impl WidgetImpl for MuteFiltersPage {}
+
+    // Trait shared by all PreferencesPages
+    impl PreferencesPageImpl for MuteFiltersPage {}
+}
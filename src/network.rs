@@ -1,8 +1,20 @@
-use futures::{stream::iter, StreamExt};
-use reqwest::Client;
-use serde::Deserialize;
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::atomic::{AtomicBool, AtomicU32, AtomicU64, AtomicUsize, Ordering};
+use std::time::Duration;
 
-#[derive(Deserialize)]
+use futures::{stream::iter, Stream, StreamExt};
+use gettextrs::gettext;
+use reqwest::{
+    header::{CONTENT_TYPE, IF_MODIFIED_SINCE, IF_NONE_MATCH},
+    Client, RequestBuilder, Response, StatusCode,
+};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+
+use crate::data_usage::{record_download, transfer_size, DataCategory};
+use crate::http_cache::{http_cache, CacheEntry};
+
+#[derive(Serialize, Deserialize, Clone)]
 pub struct Item {
     pub id: u32,
     pub deleted: Option<bool>,
@@ -22,7 +34,7 @@ pub struct Item {
 }
 
 #[allow(non_camel_case_types)]
-#[derive(Deserialize, Debug, Clone, Copy)]
+#[derive(Serialize, Deserialize, Debug, Clone, Copy)]
 pub enum Type {
     job,
     story,
@@ -31,49 +43,515 @@ pub enum Type {
     pollopt,
 }
 
+// the story lists offered by the Hacker News API
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Feed {
+    Top,
+    New,
+    Best,
+    Ask,
+    Show,
+    Job,
+}
+
+impl Feed {
+    // parses the feed names used in the settings, falling back to the top stories
+    pub fn from_name(name: &str) -> Feed {
+        match name {
+            "new" => Feed::New,
+            "best" => Feed::Best,
+            "ask" => Feed::Ask,
+            "show" => Feed::Show,
+            "job" => Feed::Job,
+            _ => Feed::Top,
+        }
+    }
+
+    pub fn title(&self) -> String {
+        match self {
+            Feed::Top => gettext("Top Stories"),
+            Feed::New => gettext("New Stories"),
+            Feed::Best => gettext("Best Stories"),
+            Feed::Ask => gettext("Ask HN"),
+            Feed::Show => gettext("Show HN"),
+            Feed::Job => gettext("Jobs"),
+        }
+    }
+
+    fn url(&self) -> &'static str {
+        match self {
+            Feed::Top => "https://hacker-news.firebaseio.com/v0/topstories.json",
+            Feed::New => "https://hacker-news.firebaseio.com/v0/newstories.json",
+            Feed::Best => "https://hacker-news.firebaseio.com/v0/beststories.json",
+            Feed::Ask => "https://hacker-news.firebaseio.com/v0/askstories.json",
+            Feed::Show => "https://hacker-news.firebaseio.com/v0/showstories.json",
+            Feed::Job => "https://hacker-news.firebaseio.com/v0/jobstories.json",
+        }
+    }
+}
+
+// what the feed page lists: one of the feeds, every story from a site, or the results of a search.
+// sites and searches go through the Algolia search API, since the Hacker News API can't do either
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub enum FeedSource {
+    Feed(Feed),
+    Site(String),
+    Search(String),
+}
+
+impl FeedSource {
+    pub fn title(&self) -> String {
+        match self {
+            FeedSource::Feed(feed) => feed.title(),
+            FeedSource::Site(domain) => domain.clone(),
+            FeedSource::Search(query) => format!("“{}”", query),
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct SearchResponse {
+    hits: Vec<SearchHit>,
+}
+
+#[derive(Deserialize)]
+struct SearchHit {
+    #[serde(rename = "objectID")]
+    object_id: String,
+}
+
+#[derive(Deserialize)]
+pub struct User {
+    pub id: String,
+    pub created: i64,
+    pub karma: u32,
+    pub about: Option<String>,
+}
+
 const ITEM_URL: &str = "https://hacker-news.firebaseio.com/v0/item/";
 const ITEM_URL_TRAIL: &str = ".json";
-const TOP_STORIES_URL: &str = "https://hacker-news.firebaseio.com/v0/topstories.json";
+const USER_URL: &str = "https://hacker-news.firebaseio.com/v0/user/";
+const SEARCH_URL: &str = "https://hn.algolia.com/api/v1/search";
+// comments are never nested deeper than this, so following parents further means something is off
+const MAX_THREAD_DEPTH: usize = 256;
+const WEBSITE_ITEM_URL: &str = "https://news.ycombinator.com/item?id=";
+const WEBSITE_USER_URL: &str = "https://news.ycombinator.com/user?id=";
+const WEBSITE_REPLY_URL: &str = "https://news.ycombinator.com/reply?id=";
 
-pub async fn fetch_stories(client: &Client) -> Result<Vec<Item>, String> {
-    let mut stories: Vec<Item> = vec![];
+// the page of an item on the Hacker News website, for people rather than the API
+pub fn website_item_url(id: u32) -> String {
+    format!("{}{}", WEBSITE_ITEM_URL, id)
+}
 
-    let story_ids: Vec<u32> = match fetch_ids(client).await {
-        Ok(s) => s[..20].to_vec(),
-        Err(e) => return Err(e.to_string()),
-    };
+pub fn website_user_url(user: &str) -> String {
+    format!("{}{}", WEBSITE_USER_URL, user)
+}
 
-    let requests = iter(story_ids.clone())
-        .map(|id| async move {
-            let request = client
-                .get(format!("{}{}{}", ITEM_URL, id, ITEM_URL_TRAIL))
-                .send()
-                .await?;
-            request.json::<Item>().await
-        })
-        .buffered(story_ids.len());
+pub fn website_reply_url(id: u32) -> String {
+    format!("{}{}", WEBSITE_REPLY_URL, id)
+}
+
+// set when the app was started with --offline, in which case nothing goes out to the network
+static OFFLINE: AtomicBool = AtomicBool::new(false);
+
+pub fn set_offline(offline: bool) {
+    OFFLINE.store(offline, Ordering::Relaxed);
+}
+
+pub fn is_offline() -> bool {
+    OFFLINE.load(Ordering::Relaxed)
+}
+
+// how many requests a fetch keeps in flight at once, and how long each of them may take, both set in the preferences
+static MAX_CONCURRENT_REQUESTS: AtomicUsize = AtomicUsize::new(8);
+static REQUEST_TIMEOUT_SECS: AtomicU64 = AtomicU64::new(15);
+
+pub fn set_max_concurrent_requests(max: usize) {
+    MAX_CONCURRENT_REQUESTS.store(max.max(1), Ordering::Relaxed);
+}
+
+fn max_concurrent_requests() -> usize {
+    MAX_CONCURRENT_REQUESTS.load(Ordering::Relaxed)
+}
+
+pub fn set_request_timeout(secs: u64) {
+    REQUEST_TIMEOUT_SECS.store(secs, Ordering::Relaxed);
+}
+
+fn request_timeout() -> Duration {
+    Duration::from_secs(REQUEST_TIMEOUT_SECS.load(Ordering::Relaxed))
+}
 
-    let responses: Vec<Result<Item, reqwest::Error>> = requests.collect().await;
+// how long responses fetched ahead of time stay fresh, in seconds, so the comments prefetched as a card
+// scrolled by are still served from the cache when the story is opened a few minutes later
+const PREFETCH_MAX_AGE: i64 = 10 * 60;
+
+tokio::task_local! {
+    // set while comments are being prefetched
+    static PREFETCHING: bool;
+}
+
+// the pages saved for offline reading, and the most of one that is downloaded
+const ARTICLE_CONTENT_TYPES: [&str; 2] = ["text/html", "application/xhtml+xml"];
+const MAX_ARTICLE_SIZE: usize = 5 * 1024 * 1024;
+
+// requests failing for reasons that may well go away are tried again this many times,
+// waiting twice as long before each attempt as before the last
+const MAX_RETRIES: u32 = 3;
+const RETRY_BASE_DELAY: Duration = Duration::from_millis(250);
+
+// what happened to the requests sent since the app started, shown in the preferences
+static REQUESTS_SENT: AtomicU32 = AtomicU32::new(0);
+static REQUESTS_RETRIED: AtomicU32 = AtomicU32::new(0);
+static REQUESTS_FAILED: AtomicU32 = AtomicU32::new(0);
+
+#[derive(Clone, Copy, Debug, Default)]
+pub struct RequestStats {
+    pub sent: u32,
+    pub retried: u32,
+    pub failed: u32,
+}
+
+pub fn request_stats() -> RequestStats {
+    RequestStats {
+        sent: REQUESTS_SENT.load(Ordering::Relaxed),
+        retried: REQUESTS_RETRIED.load(Ordering::Relaxed),
+        failed: REQUESTS_FAILED.load(Ordering::Relaxed),
+    }
+}
 
-    for response in responses {
-        match response {
-            Ok(item) => {
-                stories.push(item);
+#[derive(Debug)]
+pub enum FetchError {
+    Offline,
+    // an id that can't name anything on Hacker News, which isn't sent off at all
+    InvalidId(String),
+    // a response that isn't what was asked for, like a page that turns out to be a video
+    Rejected(String),
+    Http(reqwest::Error),
+    Decode(serde_json::Error),
+}
+
+impl fmt::Display for FetchError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FetchError::Offline => write!(f, "Not fetching anything while offline"),
+            FetchError::InvalidId(id) => write!(f, "Not a valid id: {}", id),
+            FetchError::Rejected(reason) => write!(f, "{}", reason),
+            FetchError::Http(e) => write!(f, "{}", e),
+            FetchError::Decode(e) => write!(f, "Could not decode response: {}", e),
+        }
+    }
+}
+
+impl FetchError {
+    // timeouts, dropped connections, rate limiting and server errors are worth another try,
+    // anything else, like an item that doesn't exist, will fail the same way again
+    pub fn is_transient(&self) -> bool {
+        match self {
+            FetchError::Offline | FetchError::InvalidId(_) | FetchError::Rejected(_) | FetchError::Decode(_) => false,
+            FetchError::Http(e) => {
+                e.is_timeout()
+                    || e.is_connect()
+                    || e.is_body()
+                    || e.status().is_some_and(|status| {
+                        status.is_server_error() || status == StatusCode::TOO_MANY_REQUESTS
+                    })
             }
-            Err(_e_) => (),
         }
     }
 
-    Ok(stories)
+    // whether the request actually went out and failed, as opposed to not being made while offline
+    pub fn is_failure(&self) -> bool {
+        !matches!(self, FetchError::Offline)
+    }
+}
+
+impl From<reqwest::Error> for FetchError {
+    fn from(e: reqwest::Error) -> Self {
+        FetchError::Http(e)
+    }
 }
 
-pub async fn fetch_ids(client: &Client) -> Result<Vec<u32>, reqwest::Error> {
-    let body = client
-        .get(TOP_STORIES_URL)
-        .send()
-        .await?
-        .json::<Vec<u32>>()
-        .await?;
+// every request to the APIs goes through here.
+// responses are served from the cache while they are fresh, and while offline no matter how old they are.
+// past that, the server is asked whether they have changed, so unchanged ones don't have to be downloaded again
+async fn get_json<T: DeserializeOwned>(category: DataCategory, request: RequestBuilder) -> Result<T, FetchError> {
+    let url = request
+        .try_clone()
+        .expect("GET requests have no body that could keep them from being cloned.")
+        .build()?
+        .url()
+        .to_string();
+    let cached = http_cache().get(&url).await;
+
+    let body = match cached {
+        Some(entry) if entry.is_fresh() || is_offline() => entry.body,
+        _ if is_offline() => return Err(FetchError::Offline),
+        cached => fetch_body(category, request, &url, cached).await?,
+    };
+
+    serde_json::from_str(&body).map_err(FetchError::Decode)
+}
+
+// sends a request with a timeout, retrying it with exponential backoff if it fails for a transient reason
+async fn fetch_body(
+    category: DataCategory,
+    request: RequestBuilder,
+    url: &str,
+    cached: Option<CacheEntry>,
+) -> Result<String, FetchError> {
+    let request = request
+        .timeout(request_timeout())
+        // Firebase only sends ETags when asked for them
+        .header("X-Firebase-ETag", "true");
+    let mut attempt: u32 = 0;
+
+    loop {
+        REQUESTS_SENT.fetch_add(1, Ordering::Relaxed);
+        let attempt_request = request
+            .try_clone()
+            .expect("GET requests have no body that could keep them from being cloned.");
+
+        match send_conditional(category, attempt_request, url, cached.clone()).await {
+            Ok(body) => return Ok(body),
+            Err(e) if e.is_transient() && attempt < MAX_RETRIES => {
+                REQUESTS_RETRIED.fetch_add(1, Ordering::Relaxed);
+                tokio::time::sleep(RETRY_BASE_DELAY * 2u32.pow(attempt)).await;
+                attempt += 1;
+            }
+            Err(e) => {
+                REQUESTS_FAILED.fetch_add(1, Ordering::Relaxed);
+                return Err(e);
+            }
+        }
+    }
+}
+
+// asks for the response only if it differs from the cached one, and caches whatever comes back
+async fn send_conditional(
+    category: DataCategory,
+    mut request: RequestBuilder,
+    url: &str,
+    cached: Option<CacheEntry>,
+) -> Result<String, FetchError> {
+    if let Some(entry) = cached.as_ref().filter(|entry| entry.can_revalidate()) {
+        if let Some(etag) = &entry.etag {
+            request = request.header(IF_NONE_MATCH, etag);
+        }
+        if let Some(last_modified) = &entry.last_modified {
+            request = request.header(IF_MODIFIED_SINCE, last_modified);
+        }
+    }
+
+    let response = request.send().await?;
+
+    if response.status() == StatusCode::NOT_MODIFIED {
+        // a revalidation costs no more than its headers
+        record_download(category, transfer_size(response.headers(), 0));
+        if let Some(entry) = cached {
+            let body = entry.body.clone();
+            http_cache().store(url, keep_prefetched_fresh(entry.revalidated(response.headers())));
+            return Ok(body);
+        }
+    }
+
+    let response = response.error_for_status()?;
+    let headers = response.headers().clone();
+    let bytes = response.bytes().await?;
+    record_download(category, transfer_size(&headers, bytes.len()));
+    let body = String::from_utf8_lossy(&bytes).into_owned();
+    http_cache().store(url, keep_prefetched_fresh(CacheEntry::new(body.clone(), &headers)));
 
     Ok(body)
-}
\ No newline at end of file
+}
+
+fn keep_prefetched_fresh(mut entry: CacheEntry) -> CacheEntry {
+    if PREFETCHING.try_with(|prefetching| *prefetching).unwrap_or(false) {
+        entry.max_age = entry.max_age.max(PREFETCH_MAX_AGE);
+    }
+    entry
+}
+
+// fetches the web page a story links to, for saving its text for offline reading.
+// pages aren't cached like API responses, since they are only fetched when the user asks to save them.
+// only web pages are taken, and only up to a size no article comes near, so a link to a video or a disk image
+// isn't downloaded whole just to find there's no text in it
+pub async fn fetch_article(client: &Client, url: &str) -> Result<String, FetchError> {
+    if is_offline() {
+        return Err(FetchError::Offline);
+    }
+
+    REQUESTS_SENT.fetch_add(1, Ordering::Relaxed);
+    let result = download_article(client, url).await;
+    if result.is_err() {
+        REQUESTS_FAILED.fetch_add(1, Ordering::Relaxed);
+    }
+    result
+}
+
+async fn download_article(client: &Client, url: &str) -> Result<String, FetchError> {
+    let response = client.get(url).timeout(request_timeout()).send().await?.error_for_status()?;
+    let headers = response.headers().clone();
+
+    let content_type = headers
+        .get(CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok())
+        .unwrap_or_default()
+        .to_ascii_lowercase();
+    if !ARTICLE_CONTENT_TYPES.iter().any(|article_type| content_type.starts_with(article_type)) {
+        record_download(DataCategory::Articles, transfer_size(&headers, 0));
+        return Err(FetchError::Rejected(format!("{} is not a web page but {}", url, content_type)));
+    }
+    let body = read_body(response, MAX_ARTICLE_SIZE, DataCategory::Articles).await?;
+
+    Ok(String::from_utf8_lossy(&body).into_owned())
+}
+
+// reads the body of a response, as long as it is no bigger than `max_size`, counting it towards `category`.
+// a body that says it is bigger isn't downloaded at all. the length may be missing or wrong though,
+// so the body is counted as it comes in as well, and given up on once it passes the limit
+pub async fn read_body(mut response: Response, max_size: usize, category: DataCategory) -> Result<Vec<u8>, FetchError> {
+    let url = response.url().to_string();
+    let too_big = || FetchError::Rejected(format!("{} is bigger than {} bytes", url, max_size));
+    if response.content_length().is_some_and(|length| length > max_size as u64) {
+        return Err(too_big());
+    }
+
+    let headers = response.headers().clone();
+    let mut body: Vec<u8> = vec![];
+    let result = loop {
+        match response.chunk().await {
+            Ok(Some(chunk)) if body.len() + chunk.len() > max_size => {
+                body.extend_from_slice(&chunk);
+                break Err(too_big());
+            }
+            Ok(Some(chunk)) => body.extend_from_slice(&chunk),
+            Ok(None) => break Ok(()),
+            Err(e) => break Err(e.into()),
+        }
+    };
+    record_download(category, transfer_size(&headers, body.len()));
+
+    result.map(|_| body)
+}
+
+// fetches the items with the given ids a few at a time,
+// yielding each in the order of the ids as soon as it and the ones before it are in
+pub fn fetch_items(client: &Client, ids: Vec<u32>) -> impl Stream<Item = Result<Item, FetchError>> + '_ {
+    iter(ids)
+        .map(move |id| fetch_item(client, id))
+        .buffered(max_concurrent_requests())
+}
+
+pub async fn fetch_ids(client: &Client, feed: Feed) -> Result<Vec<u32>, FetchError> {
+    get_json(DataCategory::Feeds, client.get(feed.url())).await
+}
+
+pub async fn fetch_source_ids(client: &Client, source: &FeedSource, count: usize) -> Result<Vec<u32>, FetchError> {
+    match source {
+        FeedSource::Feed(feed) => fetch_ids(client, *feed).await,
+        FeedSource::Site(domain) => {
+            fetch_search_ids(client, &[("query", domain.as_str()), ("restrictSearchableAttributes", "url")], count).await
+        }
+        FeedSource::Search(query) => fetch_search_ids(client, &[("query", query.as_str())], count).await,
+    }
+}
+
+async fn fetch_search_ids(client: &Client, query: &[(&str, &str)], count: usize) -> Result<Vec<u32>, FetchError> {
+    let request = client
+        .get(SEARCH_URL)
+        .query(query)
+        .query(&[("tags", "story"), ("hitsPerPage", count.to_string().as_str())]);
+    let body: SearchResponse = get_json(DataCategory::Feeds, request).await?;
+
+    Ok(body.hits.iter().filter_map(|hit| hit.object_id.parse().ok()).collect())
+}
+
+// Hacker News user names are made of letters, digits, dashes and underscores only,
+// which also keeps anything that would change the meaning of the URL out of it
+pub fn is_valid_user_id(id: &str) -> bool {
+    !id.is_empty() && id.chars().all(|character| character.is_ascii_alphanumeric() || character == '-' || character == '_')
+}
+
+pub async fn fetch_user(client: &Client, id: &str) -> Result<User, FetchError> {
+    if !is_valid_user_id(id) {
+        return Err(FetchError::InvalidId(id.to_string()));
+    }
+    get_json(DataCategory::Users, client.get(format!("{}{}{}", USER_URL, id, ITEM_URL_TRAIL))).await
+}
+
+// follows the parents of an item up to the story it belongs to.
+// returns the story together with the id of the comment the walk started from, if it did start from one
+pub async fn fetch_root_story(client: &Client, id: u32) -> Result<(Item, Option<u32>), FetchError> {
+    let mut item = fetch_item(client, id).await?;
+    let comment_id = matches!(item.r#type, Some(Type::comment)).then_some(id);
+
+    for _ in 0..MAX_THREAD_DEPTH {
+        match (item.r#type, item.parent) {
+            (Some(Type::comment), Some(parent)) => item = fetch_item(client, parent).await?,
+            _ => break,
+        }
+    }
+
+    Ok((item, comment_id))
+}
+
+pub async fn fetch_item(client: &Client, id: u32) -> Result<Item, FetchError> {
+    get_json(DataCategory::Items, client.get(format!("{}{}{}", ITEM_URL, id, ITEM_URL_TRAIL))).await
+}
+
+// fetches the whole comment tree below a story one level at a time,
+// and returns the comments in reading order together with their depth in the thread
+pub async fn fetch_comments(client: &Client, story: &Item) -> Vec<(Item, u32)> {
+    let mut fetched = fetch_comment_levels(client, story, usize::MAX).await;
+
+    // walk the tree depth first so that replies end up directly below their parent
+    let mut comments: Vec<(Item, u32)> = vec![];
+    let mut stack: Vec<(u32, u32)> = story.kids.iter().flatten().rev().map(|id| (*id, 0)).collect();
+
+    while let Some((id, depth)) = stack.pop() {
+        if let Some(item) = fetched.remove(&id) {
+            stack.extend(item.kids.iter().flatten().rev().map(|kid| (*kid, depth + 1)));
+            comments.push((item, depth));
+        }
+    }
+
+    comments
+}
+
+// fetches the top levels of a story's comment tree just so they are in the cache when the story is opened
+pub async fn prefetch_comments(client: &Client, story_id: u32, levels: usize) -> Result<(), FetchError> {
+    PREFETCHING
+        .scope(true, async {
+            let story = fetch_item(client, story_id).await?;
+            fetch_comment_levels(client, &story, levels).await;
+            Ok::<(), FetchError>(())
+        })
+        .await
+}
+
+// fetches up to `levels` levels of the comment tree below a story, keyed by item id
+async fn fetch_comment_levels(client: &Client, story: &Item, levels: usize) -> HashMap<u32, Item> {
+    let mut fetched: HashMap<u32, Item> = HashMap::new();
+    let mut level: Vec<u32> = story.kids.clone().unwrap_or_default();
+
+    for _ in 0..levels {
+        if level.is_empty() {
+            break;
+        }
+
+        let responses: Vec<Result<Item, FetchError>> = iter(level.clone())
+            .map(|id| fetch_item(client, id))
+            .buffered(max_concurrent_requests())
+            .collect()
+            .await;
+
+        level = vec![];
+        for item in responses.into_iter().flatten() {
+            level.extend(item.kids.iter().flatten());
+            fetched.insert(item.id, item);
+        }
+    }
+
+    fetched
+}
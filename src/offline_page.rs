@@ -0,0 +1,175 @@
+use adw::prelude::ActionRowExt;
+use adw::subclass::prelude::NavigationPageImpl;
+use glib::subclass::{
+    types::{ObjectSubclass, ObjectSubclassExt},
+    InitializingObject,
+};
+use gettextrs::gettext;
+use gtk::glib::{self, clone, DateTime, Object};
+use gtk::prelude::{ButtonExt, ToVariant};
+use gtk::subclass::prelude::ObjectSubclassIsExt;
+use gtk::subclass::widget::WidgetClassExt;
+use gtk::subclass::{
+    prelude::{ObjectImpl, ObjectImplExt},
+    widget::{CompositeTemplateClass, CompositeTemplateInitializingExt, WidgetImpl},
+};
+use gtk::{Align, Button, CompositeTemplate, ListBox, Stack, TemplateChild};
+use std::cell::RefCell;
+
+use crate::i18n::fill;
+use crate::offline_store::{OfflineIndex, SavedStory};
+
+glib::wrapper! {
+    pub struct OfflinePage(ObjectSubclass<imp::OfflinePage>)
+        @extends adw::NavigationPage, gtk::Widget,
+        @implements gtk::Accessible, gtk::Buildable,
+                    gtk::ConstraintTarget;
+}
+
+impl Default for OfflinePage {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl OfflinePage {
+    pub fn new() -> Self {
+        Object::builder().build()
+    }
+
+    // adds a story that has just been saved, or moves it to the top if it was saved before
+    pub fn add_saved(&self, saved_story: SavedStory) {
+        let mut offline_index = self.imp().offline_index.borrow_mut();
+        offline_index.add(saved_story);
+        offline_index.save();
+        drop(offline_index);
+
+        self.update_rows();
+    }
+
+    fn remove_saved(&self, id: u32) {
+        let mut offline_index = self.imp().offline_index.borrow_mut();
+        offline_index.remove(id);
+        offline_index.save();
+        drop(offline_index);
+
+        self.update_rows();
+    }
+
+    // rebuilds the list from the index, there are rarely enough saved stories for this to matter
+    fn update_rows(&self) {
+        let imp = self.imp();
+        imp.saved_list.remove_all();
+
+        let offline_index = imp.offline_index.borrow();
+        for saved_story in offline_index.stories() {
+            imp.saved_list.append(&self.saved_row(saved_story));
+        }
+
+        if offline_index.stories().is_empty() {
+            imp.stack.set_visible_child(&imp.empty_status.get());
+        } else {
+            imp.stack.set_visible_child(&imp.list_scrolled_window.get());
+        }
+    }
+
+    // a row opening the saved story, with a button to delete it again
+    fn saved_row(&self, saved_story: &SavedStory) -> adw::ActionRow {
+        let saved_at = DateTime::from_unix_local(saved_story.saved_at)
+            // Translators: the date a story was saved, see the GLib documentation of g_date_time_format
+            .and_then(|saved_at| saved_at.format(&gettext("%B %-d, %Y")))
+            .map(|saved_at| saved_at.to_string())
+            .unwrap_or_default();
+        let mut subtitle_parts: Vec<String> = vec![];
+        if !saved_story.domain.is_empty() {
+            subtitle_parts.push(saved_story.domain.clone());
+        }
+        subtitle_parts.push(fill(&gettext("Saved {}"), &[&saved_at]));
+        if saved_story.has_article {
+            subtitle_parts.push(gettext("Article included"));
+        }
+
+        let row = adw::ActionRow::builder()
+            .title(saved_story.title.as_str())
+            .subtitle(subtitle_parts.join(" · ").as_str())
+            .use_markup(false)
+            .activatable(true)
+            .action_name("app.open-story")
+            .action_target(&saved_story.id.to_variant())
+            .build();
+
+        let remove_button = Button::builder()
+            .icon_name("user-trash-symbolic")
+            .tooltip_text(gettext("Delete"))
+            .valign(Align::Center)
+            .css_classes(["flat"])
+            .build();
+        let id = saved_story.id;
+        remove_button.connect_clicked(clone!(
+            #[weak(rename_to = offline_page)]
+            self,
+            move |_| offline_page.remove_saved(id)
+        ));
+
+        row.add_suffix(&remove_button);
+        row
+    }
+}
+
+mod imp {
+    use super::*;
+
+    // ANCHOR: struct_and_subclass
+    // Object holding the state
+    #[derive(CompositeTemplate, Default)]
+    #[template(file = "src/ui/offline_page.blp")]
+    pub struct OfflinePage {
+        #[template_child]
+        pub stack: TemplateChild<Stack>,
+        #[template_child]
+        pub empty_status: TemplateChild<adw::StatusPage>,
+        #[template_child]
+        pub list_scrolled_window: TemplateChild<gtk::ScrolledWindow>,
+        #[template_child]
+        pub saved_list: TemplateChild<ListBox>,
+        pub offline_index: RefCell<OfflineIndex>,
+    }
+
+    // The central trait for subclassing a GObject
+    #[glib::object_subclass]
+    impl ObjectSubclass for OfflinePage {
+        // `NAME` needs to match `class` attribute of template
+        const NAME: &'static str = "OfflinePage";
+        type Type = super::OfflinePage;
+        type ParentType = adw::NavigationPage;
+
+        fn class_init(klass: &mut Self::Class) {
+            klass.bind_template();
+        }
+
+        fn instance_init(obj: &InitializingObject<Self>) {
+            obj.init_template();
+        }
+    }
+    // ANCHOR_END: struct_and_subclass
+
+    // ANCHOR: constructed
+    // Trait shared by all GObjects
+    impl ObjectImpl for OfflinePage {
+        fn constructed(&self) {
+            // Call "constructed" on parent
+            self.parent_constructed();
+
+            // Setup
+            self.offline_index.replace(OfflineIndex::load());
+            self.obj().update_rows();
+        }
+    }
+    // ANCHOR_END: constructed
+
+    // Trait shared by all widgets
+    impl WidgetImpl for OfflinePage {}
+
+    // Trait shared by all NavigationPages
+    impl NavigationPageImpl for OfflinePage {}
+}
@@ -0,0 +1,211 @@
+use std::fs;
+use std::path::PathBuf;
+use std::sync::OnceLock;
+
+use chrono::Utc;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+
+use crate::html::decode_entities;
+use crate::network::Item;
+use crate::storage::{data_dir, load_json, save_json};
+
+const OFFLINE_INDEX_FILE: &str = "offline.json";
+// the threads are kept one file per story, so opening one doesn't have to read all of them
+const OFFLINE_DIR: &str = "offline";
+
+// what the Offline page lists about a saved story, without having to read its thread
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct SavedStory {
+    pub id: u32,
+    pub title: String,
+    pub domain: String,
+    // unix timestamp of when the story was saved
+    pub saved_at: i64,
+    pub has_article: bool,
+}
+
+// everything needed to read a story without a network: the story, its comments in reading order
+// together with their depth, and the text of the page it links to if that could be extracted.
+// the text is kept in a file of its own, so it can be read without going through the comments.
+// saves from before that still have it in the thread, where it is moved out from the first time it is read
+#[derive(Serialize, Deserialize)]
+pub struct SavedThread {
+    pub story: Item,
+    pub comments: Vec<(Item, u32)>,
+    #[serde(default, skip_serializing)]
+    pub article: Option<String>,
+}
+
+// the stories saved for offline reading, newest first.
+// unlike the http cache these live in the data directory and are only deleted by the user
+#[derive(Serialize, Deserialize, Default)]
+pub struct OfflineIndex {
+    stories: Vec<SavedStory>,
+}
+
+impl OfflineIndex {
+    pub fn load() -> Self {
+        load_json(OFFLINE_INDEX_FILE)
+    }
+
+    pub fn save(&self) {
+        save_json(OFFLINE_INDEX_FILE, self);
+    }
+
+    pub fn stories(&self) -> &[SavedStory] {
+        &self.stories
+    }
+
+    // adds a saved story to the top, replacing an older save of the same story
+    pub fn add(&mut self, saved_story: SavedStory) {
+        self.stories.retain(|story| story.id != saved_story.id);
+        self.stories.insert(0, saved_story);
+    }
+
+    // forgets a saved story and deletes its thread
+    pub fn remove(&mut self, id: u32) {
+        self.stories.retain(|story| story.id != id);
+        delete_thread(id);
+    }
+}
+
+impl SavedThread {
+    pub fn saved_story(&self, domain: String) -> SavedStory {
+        SavedStory {
+            id: self.story.id,
+            title: self.story.title.clone().unwrap_or_default(),
+            domain,
+            saved_at: Utc::now().timestamp(),
+            has_article: self.article.is_some(),
+        }
+    }
+}
+
+fn thread_file(id: u32) -> String {
+    format!("{}/{}.json", OFFLINE_DIR, id)
+}
+
+fn article_path(id: u32) -> PathBuf {
+    offline_dir().join(format!("{}.txt", id))
+}
+
+fn offline_dir() -> PathBuf {
+    let dir = data_dir().join(OFFLINE_DIR);
+
+    if let Err(e) = fs::create_dir_all(&dir) {
+        println!("Could not create offline directory {}: {}", dir.display(), e);
+    }
+
+    dir
+}
+
+pub fn save_thread(thread: &SavedThread) {
+    offline_dir();
+    save_json(&thread_file(thread.story.id), thread);
+
+    let path = article_path(thread.story.id);
+    let result = match &thread.article {
+        Some(article) => fs::write(&path, article),
+        None if path.exists() => fs::remove_file(&path),
+        None => Ok(()),
+    };
+    if let Err(e) = result {
+        println!("Could not save {}: {}", path.display(), e);
+    }
+}
+
+// the saved thread of a story, if it was saved for offline reading
+pub fn load_thread(id: u32) -> Option<SavedThread> {
+    let mut thread = load_json::<Option<SavedThread>>(&thread_file(id))?;
+
+    if thread.article.is_some() {
+        // a save from before the text had a file of its own
+        save_thread(&thread);
+    } else {
+        thread.article = fs::read_to_string(article_path(id)).ok();
+    }
+
+    Some(thread)
+}
+
+// the text of the page a saved story links to
+pub fn load_article(id: u32) -> Option<String> {
+    match fs::read_to_string(article_path(id)) {
+        Ok(article) => Some(article),
+        // only saves from before the text had a file of its own have to be read whole
+        Err(_) if data_dir().join(thread_file(id)).exists() => load_thread(id).and_then(|thread| thread.article),
+        Err(_) => None,
+    }
+}
+
+fn delete_thread(id: u32) {
+    for path in [offline_dir().join(format!("{}.json", id)), article_path(id)] {
+        if !path.exists() {
+            continue;
+        }
+        if let Err(e) = fs::remove_file(&path) {
+            println!("Could not delete {}: {}", path.display(), e);
+        }
+    }
+}
+
+// pulls the readable text out of a web page, the way a reader mode would:
+// the text of its paragraphs and headings, with the markup, scripts and navigation left out.
+// returns None for pages without any, like those built entirely by javascript
+pub fn extract_article_text(html: &str) -> Option<String> {
+    static SKIPPED: OnceLock<Regex> = OnceLock::new();
+    static BLOCKS: OnceLock<Regex> = OnceLock::new();
+    static TAGS: OnceLock<Regex> = OnceLock::new();
+    static WHITESPACE: OnceLock<Regex> = OnceLock::new();
+
+    let skipped = SKIPPED.get_or_init(|| {
+        Regex::new(r"(?is)<(script|style|nav|header|footer|aside|form)\b.*?</(script|style|nav|header|footer|aside|form)>")
+            .expect("The skipped elements pattern is valid.")
+    });
+    let blocks = BLOCKS.get_or_init(|| {
+        Regex::new(r"(?is)<(p|h[1-6]|li|blockquote|pre)\b[^>]*>(.*?)</(p|h[1-6]|li|blockquote|pre)>")
+            .expect("The text blocks pattern is valid.")
+    });
+    let tags = TAGS.get_or_init(|| Regex::new(r"(?s)<[^>]*>").expect("The tags pattern is valid."));
+    let whitespace = WHITESPACE.get_or_init(|| Regex::new(r"\s+").expect("The whitespace pattern is valid."));
+
+    let html = skipped.replace_all(html, "");
+    let paragraphs: Vec<String> = blocks
+        .captures_iter(&html)
+        .map(|captures| {
+            let text = tags.replace_all(&captures[2], "");
+            decode_entities(whitespace.replace_all(&text, " ").trim())
+        })
+        .filter(|paragraph| !paragraph.is_empty())
+        .collect();
+
+    (!paragraphs.is_empty()).then(|| paragraphs.join("\n\n"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn paragraphs_and_headings_are_kept() {
+        let html = "<html><body><h1>Title</h1><div><p class=\"lead\">First   paragraph\n with <a href=\"/x\">a link</a>.</p><p>Second &amp; last.</p></div></body></html>";
+        assert_eq!(
+            extract_article_text(html).as_deref(),
+            Some("Title\n\nFirst paragraph with a link.\n\nSecond & last.")
+        );
+    }
+
+    #[test]
+    fn scripts_and_navigation_are_left_out() {
+        let html = "<nav><ul><li>Home</li></ul></nav><script>var p = '<p>not text</p>';</script>\
+                    <style>p { color: red }</style><article><p>Body</p></article><footer><p>Footer</p></footer>";
+        assert_eq!(extract_article_text(html).as_deref(), Some("Body"));
+    }
+
+    #[test]
+    fn pages_without_text_have_no_article() {
+        assert_eq!(extract_article_text("<div id=\"app\"></div><script src=\"app.js\"></script>"), None);
+        assert_eq!(extract_article_text("<p>   </p><p><img src=\"a.png\"></p>"), None);
+    }
+}
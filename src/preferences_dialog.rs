@@ -0,0 +1,310 @@
+use adw::subclass::prelude::{AdwDialogImpl, PreferencesDialogImpl};
+use glib::subclass::{
+    types::{ObjectSubclass, ObjectSubclassExt},
+    InitializingObject,
+};
+use adw::prelude::{ActionRowExt, ExpanderRowExt, PreferencesDialogExt, PreferencesGroupExt};
+use chrono::{Datelike, NaiveDate};
+use gettextrs::gettext;
+use gtk::gio::{self, prelude::SettingsExtManual};
+use gtk::glib::{self, Object, ToValue, ToVariant};
+use gtk::prelude::StaticTypeExt;
+use gtk::subclass::prelude::ObjectSubclassIsExt;
+use gtk::subclass::widget::WidgetClassExt;
+use gtk::subclass::{
+    prelude::{ObjectImpl, ObjectImplExt},
+    widget::{CompositeTemplateClass, CompositeTemplateInitializingExt, WidgetImpl},
+};
+use gtk::{CompositeTemplate, TemplateChild};
+use std::cell::OnceCell;
+
+use crate::data_usage::daily_usage;
+use crate::i18n::{fill, format_number};
+use crate::mute_filters_page::MuteFiltersPage;
+use crate::network::request_stats;
+use crate::settings::settings;
+
+// the values of the string settings shown as combo rows, in the order of the rows' entries
+const FEEDS: &[&str] = &["top", "new", "best", "ask", "show", "job"];
+const LINK_OPENING_MODES: &[&str] = &["comments", "browser"];
+const THEMES: &[&str] = &["system", "light", "dark"];
+const CARD_DENSITIES: &[&str] = &["comfortable", "compact"];
+const SCORE_POSITIONS: &[&str] = &["inline", "left", "right"];
+const SITE_IMAGES: &[&str] = &["none", "favicon", "thumbnail"];
+const SHARE_FORMATS: &[&str] = &["plain", "markdown"];
+const SWIPE_ACTIONS: &[&str] = &["none", "upvote", "bookmark", "hide", "mark-read", "share"];
+const DATA_SAVER_MODES: &[&str] = &["auto", "on", "off"];
+
+glib::wrapper! {
+    pub struct GliderPreferencesDialog(ObjectSubclass<imp::GliderPreferencesDialog>)
+        @extends adw::PreferencesDialog, adw::Dialog, gtk::Widget,
+        @implements gtk::Accessible, gtk::Buildable,
+                    gtk::ConstraintTarget, gtk::ShortcutManager;
+}
+
+impl Default for GliderPreferencesDialog {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl GliderPreferencesDialog {
+    pub fn new() -> Self {
+        Object::builder().build()
+    }
+
+    fn settings(&self) -> &gio::Settings {
+        self.imp().settings.get().expect("Could not get settings.")
+    }
+
+    // opens the dialog at the mute filters rather than the general preferences
+    pub fn show_mute_filters(&self) {
+        self.set_visible_page(&self.imp().mute_filters_page.get());
+    }
+
+    // calls `f` every time the user adds or removes a mute filter
+    pub fn connect_mute_filters_changed<F: Fn(&MuteFiltersPage) + 'static>(&self, f: F) -> glib::SignalHandlerId {
+        self.imp().mute_filters_page.connect_filters_changed(f)
+    }
+
+    fn setup_settings(&self) {
+        self.imp()
+            .settings
+            .set(settings())
+            .expect("`settings` should not be set before calling `setup_settings`.");
+
+        let imp = self.imp();
+        self.bind_choice("default-feed", &imp.default_feed_row.get(), FEEDS);
+        self.bind_choice("link-opening-mode", &imp.link_opening_row.get(), LINK_OPENING_MODES);
+        self.bind_choice("theme", &imp.theme_row.get(), THEMES);
+        self.bind_choice("card-density", &imp.card_density_row.get(), CARD_DENSITIES);
+        self.bind_choice("card-score-position", &imp.card_score_position_row.get(), SCORE_POSITIONS);
+        self.bind_choice("card-site-image", &imp.card_site_image_row.get(), SITE_IMAGES);
+        self.bind_choice("share-format", &imp.share_format_row.get(), SHARE_FORMATS);
+        self.bind_choice("swipe-right-action", &imp.swipe_right_row.get(), SWIPE_ACTIONS);
+        self.bind_choice("swipe-left-action", &imp.swipe_left_row.get(), SWIPE_ACTIONS);
+        self.bind_choice("data-saver", &imp.data_saver_row.get(), DATA_SAVER_MODES);
+        self.bind_uint("page-size", &imp.page_size_row.get());
+        self.bind_uint("refresh-interval", &imp.refresh_interval_row.get());
+        self.bind_uint("max-concurrent-requests", &imp.max_concurrent_requests_row.get());
+        self.bind_uint("request-timeout", &imp.request_timeout_row.get());
+        self.bind_uint("cache-size", &imp.cache_size_row.get());
+        self.bind_uint("history-max-age", &imp.history_max_age_row.get());
+
+        self.settings()
+            .bind("font-scale", &imp.font_scale_row.get(), "value")
+            .build();
+        self.settings()
+            .bind("text-scale", &imp.text_scale_row.get(), "value")
+            .build();
+        self.settings()
+            .bind("true-black", &imp.true_black_row.get(), "active")
+            .build();
+
+        let card_switches = [
+            ("card-show-domain", imp.card_show_domain_row.get()),
+            ("card-show-author", imp.card_show_author_row.get()),
+            ("card-show-time", imp.card_show_time_row.get()),
+            ("card-show-score", imp.card_show_score_row.get()),
+            ("card-show-comments", imp.card_show_comments_row.get()),
+            ("card-show-rank", imp.card_show_rank_row.get()),
+        ];
+        for (key, switch_row) in card_switches {
+            self.settings().bind(key, &switch_row, "active").build();
+        }
+    }
+
+    // a snapshot of how the requests sent so far went, taken when the dialog opens
+    fn show_request_stats(&self) {
+        let stats = request_stats();
+        self.imp().request_stats_row.set_subtitle(&fill(
+            // Translators: {0}, {1} and {2} are the numbers of requests sent, retried and failed
+            &gettext("{0} sent, {1} retried, {2} failed"),
+            &[
+                &format_number(stats.sent),
+                &format_number(stats.retried),
+                &format_number(stats.failed),
+            ],
+        ));
+    }
+
+    // a row per day with what was downloaded, which can be expanded to show what it was spent on
+    fn show_data_usage(&self) {
+        let data_usage_group = self.imp().data_usage_group.get();
+        let daily_usage = daily_usage();
+
+        if daily_usage.is_empty() {
+            data_usage_group.set_description(Some(&gettext("Nothing downloaded yet")));
+        }
+
+        for (day, categories) in daily_usage {
+            let total: u64 = categories.iter().map(|(_, bytes)| bytes).sum();
+            let day_row = adw::ExpanderRow::builder()
+                .title(format_day(day))
+                .subtitle(glib::format_size(total))
+                .build();
+
+            for (category, bytes) in categories {
+                let category_row = adw::ActionRow::builder()
+                    .title(category.title())
+                    .subtitle(glib::format_size(bytes))
+                    .css_classes(["property"])
+                    .build();
+                day_row.add_row(&category_row);
+            }
+
+            data_usage_group.add(&day_row);
+        }
+    }
+
+    // binds a string setting to a combo row whose entries follow the order of `choices`
+    fn bind_choice(&self, key: &str, combo_row: &adw::ComboRow, choices: &'static [&'static str]) {
+        self.settings()
+            .bind(key, combo_row, "selected")
+            .mapping(move |variant, _| {
+                let value = variant.str()?;
+                choices
+                    .iter()
+                    .position(|choice| *choice == value)
+                    .map(|position| (position as u32).to_value())
+            })
+            .set_mapping(move |value, _| {
+                let position = value.get::<u32>().ok()?;
+                choices
+                    .get(position as usize)
+                    .map(|choice| choice.to_variant())
+            })
+            .build();
+    }
+
+    // binds an unsigned integer setting to a spin row, which works in doubles
+    fn bind_uint(&self, key: &str, spin_row: &adw::SpinRow) {
+        self.settings()
+            .bind(key, spin_row, "value")
+            .mapping(|variant, _| variant.get::<u32>().map(|value| (value as f64).to_value()))
+            .set_mapping(|value, _| {
+                value
+                    .get::<f64>()
+                    .ok()
+                    .map(|value| (value.round() as u32).to_variant())
+            })
+            .build();
+    }
+}
+
+// the name of a day in the user's language, like "Monday, October 19"
+fn format_day(day: NaiveDate) -> String {
+    glib::DateTime::from_local(day.year(), day.month() as i32, day.day() as i32, 0, 0, 0.0)
+        // Translators: a day in the data usage list, see the GLib documentation of g_date_time_format
+        .and_then(|date_time| date_time.format(&gettext("%A, %B %-d")))
+        .map(|formatted| formatted.to_string())
+        .unwrap_or_else(|_| day.to_string())
+}
+
+mod imp {
+    use super::*;
+
+    // Object holding the state
+    #[derive(CompositeTemplate, Default)]
+    #[template(file = "src/ui/preferences_dialog.blp")]
+    pub struct GliderPreferencesDialog {
+        #[template_child]
+        pub default_feed_row: TemplateChild<adw::ComboRow>,
+        #[template_child]
+        pub page_size_row: TemplateChild<adw::SpinRow>,
+        #[template_child]
+        pub refresh_interval_row: TemplateChild<adw::SpinRow>,
+        #[template_child]
+        pub link_opening_row: TemplateChild<adw::ComboRow>,
+        #[template_child]
+        pub theme_row: TemplateChild<adw::ComboRow>,
+        #[template_child]
+        pub true_black_row: TemplateChild<adw::SwitchRow>,
+        #[template_child]
+        pub font_scale_row: TemplateChild<adw::SpinRow>,
+        #[template_child]
+        pub text_scale_row: TemplateChild<adw::SpinRow>,
+        #[template_child]
+        pub card_density_row: TemplateChild<adw::ComboRow>,
+        #[template_child]
+        pub card_show_domain_row: TemplateChild<adw::SwitchRow>,
+        #[template_child]
+        pub card_show_author_row: TemplateChild<adw::SwitchRow>,
+        #[template_child]
+        pub card_show_time_row: TemplateChild<adw::SwitchRow>,
+        #[template_child]
+        pub card_show_score_row: TemplateChild<adw::SwitchRow>,
+        #[template_child]
+        pub card_show_comments_row: TemplateChild<adw::SwitchRow>,
+        #[template_child]
+        pub card_show_rank_row: TemplateChild<adw::SwitchRow>,
+        #[template_child]
+        pub card_score_position_row: TemplateChild<adw::ComboRow>,
+        #[template_child]
+        pub card_site_image_row: TemplateChild<adw::ComboRow>,
+        #[template_child]
+        pub share_format_row: TemplateChild<adw::ComboRow>,
+        #[template_child]
+        pub swipe_right_row: TemplateChild<adw::ComboRow>,
+        #[template_child]
+        pub swipe_left_row: TemplateChild<adw::ComboRow>,
+        #[template_child]
+        pub max_concurrent_requests_row: TemplateChild<adw::SpinRow>,
+        #[template_child]
+        pub request_timeout_row: TemplateChild<adw::SpinRow>,
+        #[template_child]
+        pub request_stats_row: TemplateChild<adw::ActionRow>,
+        #[template_child]
+        pub data_saver_row: TemplateChild<adw::ComboRow>,
+        #[template_child]
+        pub data_usage_group: TemplateChild<adw::PreferencesGroup>,
+        #[template_child]
+        pub cache_size_row: TemplateChild<adw::SpinRow>,
+        #[template_child]
+        pub history_max_age_row: TemplateChild<adw::SpinRow>,
+        #[template_child]
+        pub mute_filters_page: TemplateChild<MuteFiltersPage>,
+        pub settings: OnceCell<gio::Settings>,
+    }
+
+    // The central trait for subclassing a GObject
+    #[glib::object_subclass]
+    impl ObjectSubclass for GliderPreferencesDialog {
+        // `NAME` needs to match `class` attribute of template
+        const NAME: &'static str = "GliderPreferencesDialog";
+        type Type = super::GliderPreferencesDialog;
+        type ParentType = adw::PreferencesDialog;
+
+        fn class_init(klass: &mut Self::Class) {
+            MuteFiltersPage::ensure_type();
+            klass.bind_template();
+        }
+
+        fn instance_init(obj: &InitializingObject<Self>) {
+            obj.init_template();
+        }
+    }
+
+    // Trait shared by all GObjects
+    impl ObjectImpl for GliderPreferencesDialog {
+        fn constructed(&self) {
+            // Call "constructed" on parent
+            self.parent_constructed();
+
+            // Setup
+            let obj = self.obj();
+            obj.setup_settings();
+            obj.show_request_stats();
+            obj.show_data_usage();
+        }
+    }
+
+    // Trait shared by all widgets
+    impl WidgetImpl for GliderPreferencesDialog {}
+
+    // Trait shared by all dialogs
+    impl AdwDialogImpl for GliderPreferencesDialog {}
+
+    // Trait shared by all PreferencesDialogs
+    impl PreferencesDialogImpl for GliderPreferencesDialog {}
+}
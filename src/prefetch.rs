@@ -0,0 +1,91 @@
+use std::cell::RefCell;
+use std::collections::{HashMap, HashSet};
+use std::sync::OnceLock;
+use std::time::Duration;
+
+use gtk::gio::{self, prelude::NetworkMonitorExt};
+use reqwest::Client;
+use tokio::sync::Semaphore;
+use tokio::task::AbortHandle;
+
+use crate::data_saver::is_data_saver_active;
+use crate::network::prefetch_comments;
+use crate::transform::runtime;
+
+// how many levels of replies are fetched ahead of time
+const PREFETCH_LEVELS: usize = 2;
+// how many stories have their comments prefetched at once, kept low to leave room for what the user asked for
+const PREFETCH_CONCURRENCY: usize = 2;
+// cards only scrolled past during a fling are unbound again before this is up, so they are never prefetched
+const PREFETCH_DELAY: Duration = Duration::from_millis(400);
+
+fn prefetch_permits() -> &'static Semaphore {
+    static PERMITS: OnceLock<Semaphore> = OnceLock::new();
+    PERMITS.get_or_init(|| Semaphore::new(PREFETCH_CONCURRENCY))
+}
+
+// fetches the comments of the stories on screen in the background, so opening them feels instant.
+// a story scrolled out of view before its turn came is dropped from the queue
+pub struct CommentPrefetcher {
+    client: Client,
+    pending: RefCell<HashMap<u32, AbortHandle>>,
+    // stories prefetched so far, which aren't fetched again when their cards come back into view
+    prefetched: RefCell<HashSet<u32>>,
+}
+
+impl CommentPrefetcher {
+    pub fn new(client: Client) -> Self {
+        CommentPrefetcher {
+            client,
+            pending: RefCell::default(),
+            prefetched: RefCell::default(),
+        }
+    }
+
+    // queues the comments of a story for prefetching, only on unmetered connections and when not saving data
+    pub fn request(&self, story_id: u32) {
+        if is_data_saver_active() || gio::NetworkMonitor::default().is_network_metered() {
+            return;
+        }
+
+        // finished prefetches stay in `prefetched`, so a story is prefetched at most once per batch of stories
+        self.pending.borrow_mut().retain(|_, abort_handle| !abort_handle.is_finished());
+        if self.pending.borrow().contains_key(&story_id) || !self.prefetched.borrow_mut().insert(story_id) {
+            return;
+        }
+
+        let client = self.client.clone();
+        let abort_handle = runtime()
+            .spawn(async move {
+                tokio::time::sleep(PREFETCH_DELAY).await;
+                let _permit = prefetch_permits()
+                    .acquire()
+                    .await
+                    .expect("The prefetch semaphore is never closed.");
+
+                if let Err(e) = prefetch_comments(&client, story_id, PREFETCH_LEVELS).await {
+                    println!("Could not prefetch comments of {}: {}", story_id, e);
+                }
+            })
+            .abort_handle();
+        self.pending.borrow_mut().insert(story_id, abort_handle);
+    }
+
+    // drops the prefetch of a story whose card has scrolled out of view, unless it is already done
+    pub fn cancel(&self, story_id: u32) {
+        if let Some(abort_handle) = self.pending.borrow_mut().remove(&story_id) {
+            if !abort_handle.is_finished() {
+                abort_handle.abort();
+                self.prefetched.borrow_mut().remove(&story_id);
+            }
+        }
+    }
+
+    // forgets what was prefetched, so the comments of a new batch of stories are fetched fresh
+    pub fn reset(&self) {
+        for (_, abort_handle) in self.pending.borrow_mut().drain() {
+            abort_handle.abort();
+        }
+        self.prefetched.borrow_mut().clear();
+    }
+}
@@ -0,0 +1,31 @@
+use crate::storage::TimestampedIds;
+
+const READ_HISTORY_FILE: &str = "read_history.json";
+
+// the stories the user has opened, with when each was marked as read
+pub struct ReadHistory(TimestampedIds);
+
+impl Default for ReadHistory {
+    fn default() -> Self {
+        ReadHistory(TimestampedIds::new(READ_HISTORY_FILE, "read"))
+    }
+}
+
+impl ReadHistory {
+    // stories read more than `max_age_days` ago are forgotten
+    pub fn load(max_age_days: i64) -> Self {
+        ReadHistory(TimestampedIds::load(READ_HISTORY_FILE, "read", Some(max_age_days)))
+    }
+
+    pub fn save(&self) {
+        self.0.save();
+    }
+
+    pub fn is_read(&self, id: u32) -> bool {
+        self.0.contains(id)
+    }
+
+    pub fn mark_read(&mut self, id: u32) {
+        self.0.insert(id);
+    }
+}
@@ -0,0 +1,158 @@
+use chrono::{DateTime, Utc};
+use gettextrs::{gettext, ngettext};
+use gtk::glib::markup_escape_text;
+
+use crate::i18n::{fill, format_number};
+
+const MINUTE: i64 = 60;
+const HOUR: i64 = 60 * MINUTE;
+const DAY: i64 = 24 * HOUR;
+const WEEK: i64 = 7 * DAY;
+// calendar months and years vary in length, these are close enough for "n months ago"
+const MONTH: i64 = 30 * DAY;
+const YEAR: i64 = 365 * DAY;
+
+// how long ago something happened, in the largest unit that fits at least once
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RelativeTime {
+    JustNow,
+    Minutes(u32),
+    Hours(u32),
+    Days(u32),
+    Weeks(u32),
+    Months(u32),
+    Years(u32),
+}
+
+impl RelativeTime {
+    // times in the future, which a clock running behind the server's can produce, count as just now
+    pub fn between(time: DateTime<Utc>, now: DateTime<Utc>) -> Self {
+        let seconds = (now - time).num_seconds().max(0);
+        let count = |unit: i64| (seconds / unit).try_into().unwrap_or(u32::MAX);
+
+        if seconds < MINUTE {
+            RelativeTime::JustNow
+        } else if seconds < HOUR {
+            RelativeTime::Minutes(count(MINUTE))
+        } else if seconds < DAY {
+            RelativeTime::Hours(count(HOUR))
+        } else if seconds < WEEK {
+            RelativeTime::Days(count(DAY))
+        } else if seconds < MONTH {
+            RelativeTime::Weeks(count(WEEK))
+        } else if seconds < YEAR {
+            RelativeTime::Months(count(MONTH))
+        } else {
+            RelativeTime::Years(count(YEAR))
+        }
+    }
+
+    // the text shown to the user, in their language and with the plural form it asks for
+    pub fn to_localized_string(self) -> String {
+        let (template, count) = match self {
+            RelativeTime::JustNow => return gettext("just now"),
+            RelativeTime::Minutes(n) => (ngettext("{} minute ago", "{} minutes ago", n), n),
+            RelativeTime::Hours(n) => (ngettext("{} hour ago", "{} hours ago", n), n),
+            RelativeTime::Days(n) => (ngettext("{} day ago", "{} days ago", n), n),
+            RelativeTime::Weeks(n) => (ngettext("{} week ago", "{} weeks ago", n), n),
+            RelativeTime::Months(n) => (ngettext("{} month ago", "{} months ago", n), n),
+            RelativeTime::Years(n) => (ngettext("{} year ago", "{} years ago", n), n),
+        };
+        fill(&template, &[&format_number(count)])
+    }
+}
+
+// turns a unix timestamp from the API into "n units ago".
+// items the API sends without a time, or with one out of range, get no label rather than a made-up one
+pub fn format_relative_time(timestamp: Option<i64>, now: DateTime<Utc>) -> String {
+    timestamp
+        .and_then(|timestamp| DateTime::from_timestamp(timestamp, 0))
+        .map(|time| RelativeTime::between(time, now).to_localized_string())
+        .unwrap_or_default()
+}
+
+// the grey "n units ago" label of cards and comments, as of right now
+pub fn time_label_markup(timestamp: Option<i64>) -> String {
+    format!(
+        "<span foreground=\"grey\">{}</span>",
+        markup_escape_text(&format_relative_time(timestamp, Utc::now()))
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Duration;
+
+    fn now() -> DateTime<Utc> {
+        DateTime::from_timestamp(1_700_000_000, 0).expect("The test timestamp is in range.")
+    }
+
+    fn ago(duration: Duration) -> RelativeTime {
+        RelativeTime::between(now() - duration, now())
+    }
+
+    #[test]
+    fn less_than_a_minute_is_just_now() {
+        assert_eq!(ago(Duration::seconds(0)), RelativeTime::JustNow);
+        assert_eq!(ago(Duration::seconds(59)), RelativeTime::JustNow);
+    }
+
+    #[test]
+    fn future_times_are_just_now() {
+        assert_eq!(ago(Duration::minutes(-5)), RelativeTime::JustNow);
+    }
+
+    #[test]
+    fn fresh_items_are_counted_in_minutes() {
+        assert_eq!(ago(Duration::seconds(60)), RelativeTime::Minutes(1));
+        assert_eq!(ago(Duration::minutes(5)), RelativeTime::Minutes(5));
+        assert_eq!(ago(Duration::minutes(59)), RelativeTime::Minutes(59));
+    }
+
+    #[test]
+    fn units_switch_at_their_boundaries() {
+        assert_eq!(ago(Duration::hours(1)), RelativeTime::Hours(1));
+        assert_eq!(ago(Duration::hours(23)), RelativeTime::Hours(23));
+        assert_eq!(ago(Duration::hours(24)), RelativeTime::Days(1));
+        assert_eq!(ago(Duration::days(6)), RelativeTime::Days(6));
+        assert_eq!(ago(Duration::days(7)), RelativeTime::Weeks(1));
+        assert_eq!(ago(Duration::days(29)), RelativeTime::Weeks(4));
+        assert_eq!(ago(Duration::days(30)), RelativeTime::Months(1));
+        assert_eq!(ago(Duration::days(364)), RelativeTime::Months(12));
+        assert_eq!(ago(Duration::days(365)), RelativeTime::Years(1));
+        assert_eq!(ago(Duration::days(3 * 365 + 10)), RelativeTime::Years(3));
+    }
+
+    #[test]
+    fn months_are_not_approximated_from_weeks() {
+        // seven weeks used to come out as "2 months ago" from weeks / 4
+        assert_eq!(ago(Duration::weeks(7)), RelativeTime::Months(1));
+        assert_eq!(ago(Duration::days(75)), RelativeTime::Months(2));
+    }
+
+    #[test]
+    fn singular_and_plural_forms() {
+        assert_eq!(RelativeTime::JustNow.to_localized_string(), "just now");
+        assert_eq!(RelativeTime::Minutes(1).to_localized_string(), "1 minute ago");
+        assert_eq!(RelativeTime::Minutes(5).to_localized_string(), "5 minutes ago");
+        assert_eq!(RelativeTime::Hours(1).to_localized_string(), "1 hour ago");
+        assert_eq!(RelativeTime::Hours(2).to_localized_string(), "2 hours ago");
+        assert_eq!(RelativeTime::Days(1).to_localized_string(), "1 day ago");
+        assert_eq!(RelativeTime::Weeks(3).to_localized_string(), "3 weeks ago");
+        assert_eq!(RelativeTime::Months(1).to_localized_string(), "1 month ago");
+        assert_eq!(RelativeTime::Years(10).to_localized_string(), "10 years ago");
+    }
+
+    #[test]
+    fn missing_or_out_of_range_timestamps_have_no_label() {
+        assert_eq!(format_relative_time(None, now()), "");
+        assert_eq!(format_relative_time(Some(i64::MAX), now()), "");
+    }
+
+    #[test]
+    fn timestamps_are_formatted_relative_to_now() {
+        let two_hours_ago = now().timestamp() - 2 * HOUR;
+        assert_eq!(format_relative_time(Some(two_hours_ago), now()), "2 hours ago");
+    }
+}
@@ -0,0 +1,57 @@
+use std::collections::{HashMap, HashSet};
+
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+
+use crate::storage::{load_json, prune_older_than, save_json};
+
+const SEEN_COMMENTS_FILE: &str = "seen_comments.json";
+
+#[derive(Serialize, Deserialize, Default)]
+struct SeenStory {
+    visited_at: i64,
+    comments: HashSet<u32>,
+}
+
+// the comment ids the user has been shown for every story they have opened,
+// used to tell which comments are new when they come back to a thread
+#[derive(Serialize, Deserialize, Default)]
+pub struct SeenComments {
+    stories: HashMap<u32, SeenStory>,
+}
+
+impl SeenComments {
+    pub fn load() -> Self {
+        load_json(SEEN_COMMENTS_FILE)
+    }
+
+    pub fn save(&self) {
+        save_json(SEEN_COMMENTS_FILE, self);
+    }
+
+    // whether a comment should be highlighted as new.
+    // nothing is new on the first visit to a story, since then every comment would be
+    pub fn is_new(&self, story_id: u32, comment_id: u32) -> bool {
+        self.stories
+            .get(&story_id)
+            .is_some_and(|seen_story| !seen_story.comments.contains(&comment_id))
+    }
+
+    // estimates how many comments were added to a story since it was last opened
+    pub fn new_count(&self, story_id: u32, comments_count: u32) -> u32 {
+        self.stories.get(&story_id).map_or(0, |seen_story| {
+            comments_count.saturating_sub(seen_story.comments.len() as u32)
+        })
+    }
+
+    pub fn record(&mut self, story_id: u32, comment_ids: impl IntoIterator<Item = u32>) {
+        let seen_story = self.stories.entry(story_id).or_default();
+        seen_story.visited_at = Utc::now().timestamp();
+        seen_story.comments.extend(comment_ids);
+    }
+
+    // forget every story that was last opened more than `max_age_days` ago
+    pub fn prune(&mut self, max_age_days: i64) {
+        prune_older_than(&mut self.stories, max_age_days, |seen_story| seen_story.visited_at);
+    }
+}
@@ -0,0 +1,37 @@
+use serde::{Deserialize, Serialize};
+
+use crate::network::FeedSource;
+use crate::storage::{load_json, save_json};
+
+const SESSION_FILE: &str = "session.json";
+
+// where the user was when the app was last closed or sent to the background,
+// so the next start can pick up right there
+#[derive(Serialize, Deserialize, Default)]
+pub struct Session {
+    pub source: Option<FeedSource>,
+    pub feed_scroll_offset: f64,
+    pub story: Option<SessionStory>,
+    // the user whose profile was pushed over the feed and comments, if any
+    pub user_id: Option<String>,
+}
+
+// the story whose comments were open, and how the user had left them
+#[derive(Serialize, Deserialize, Default)]
+pub struct SessionStory {
+    pub id: u32,
+    // whether the comments were in view rather than the feed, which only matters in the collapsed layout
+    pub shown: bool,
+    pub collapsed_ids: Vec<u32>,
+    pub scroll_offset: f64,
+}
+
+impl Session {
+    pub fn load() -> Self {
+        load_json(SESSION_FILE)
+    }
+
+    pub fn save(&self) {
+        save_json(SESSION_FILE, self);
+    }
+}
@@ -0,0 +1,41 @@
+use gtk::gio::{self, SettingsSchemaSource};
+
+use crate::application::APP_ID;
+
+// the schema shares its id with the application
+pub const SCHEMA_ID: &str = APP_ID;
+
+thread_local! {
+    // opened once on the GTK thread, which everything reading the settings runs on
+    static SETTINGS: gio::Settings = open_settings();
+}
+
+// the app's settings, shared by everything that reads or watches them
+pub fn settings() -> gio::Settings {
+    SETTINGS.with(Clone::clone)
+}
+
+// opens the settings with the schema installed on the system,
+// falling back to the one compiled into the build tree for debug builds run from there
+fn open_settings() -> gio::Settings {
+    let schema = SettingsSchemaSource::default()
+        .and_then(|source| source.lookup(SCHEMA_ID, true))
+        .or_else(build_tree_schema)
+        .expect("The GSettings schema needs to be installed or compiled into the build directory.");
+
+    gio::Settings::new_full(&schema, None::<&gio::SettingsBackend>, None)
+}
+
+// the schema compiled by build.rs, so a debug build can run from the build tree without installing it first
+#[cfg(debug_assertions)]
+fn build_tree_schema() -> Option<gio::SettingsSchema> {
+    SettingsSchemaSource::from_directory(env!("OUT_DIR"), SettingsSchemaSource::default().as_ref(), false)
+        .ok()?
+        .lookup(SCHEMA_ID, false)
+}
+
+// release builds only ever use the installed schema, rather than one left behind in the tree they were built in
+#[cfg(not(debug_assertions))]
+fn build_tree_schema() -> Option<gio::SettingsSchema> {
+    None
+}
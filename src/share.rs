@@ -0,0 +1,137 @@
+use adw::prelude::*;
+use gettextrs::gettext;
+use gtk::gio::{self, prelude::SettingsExt};
+use gtk::glib;
+use gtk::UriLauncher;
+
+use crate::settings::settings;
+use crate::window::GliderCloneWindow;
+
+// how a story's title and link are put together when copying or sharing them
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ShareFormat {
+    Plain,
+    Markdown,
+}
+
+impl ShareFormat {
+    pub fn from_name(name: &str) -> Self {
+        match name {
+            "markdown" => ShareFormat::Markdown,
+            _ => ShareFormat::Plain,
+        }
+    }
+
+    // the format chosen in the preferences
+    pub fn current() -> Self {
+        ShareFormat::from_name(&settings().string("share-format"))
+    }
+
+    pub fn title_and_link(&self, title: &str, link: &str) -> String {
+        match self {
+            ShareFormat::Plain => format!("{} — {}", title, link),
+            ShareFormat::Markdown => {
+                let title = title.replace('[', "\\[").replace(']', "\\]");
+                format!("[{}]({})", title, link)
+            }
+        }
+    }
+}
+
+// puts text on the clipboard and tells the user with a toast
+pub fn copy_text(widget: &impl IsA<gtk::Widget>, text: &str, message: &str) {
+    widget.clipboard().set_text(text);
+    show_toast(widget, message);
+}
+
+// offers the ways a story can be passed on in a sheet, since desktops have no share sheet of their own:
+// copying it in the chosen format, copying just the link, or handing it to the user's mail app,
+// which goes through the OpenURI portal when sandboxed
+pub fn share_story(widget: &impl IsA<gtk::Widget>, title: &str, link: &str) {
+    let widget = widget.clone().upcast::<gtk::Widget>();
+    let text = ShareFormat::current().title_and_link(title, link);
+    let mail_uri = format!(
+        "mailto:?subject={}&body={}",
+        glib::Uri::escape_string(title, None, false),
+        glib::Uri::escape_string(&text, None, false),
+    );
+
+    let group = adw::PreferencesGroup::new();
+    let dialog = adw::Dialog::builder()
+        .title(gettext("Share"))
+        .content_width(360)
+        .build();
+
+    let add_target = |label: String, icon_name: &str, share: Box<dyn Fn(&gtk::Widget)>| {
+        let row = adw::ActionRow::builder()
+            .title(label)
+            .activatable(true)
+            .build();
+        row.add_prefix(&gtk::Image::from_icon_name(icon_name));
+        row.connect_activated(glib::clone!(
+            #[weak]
+            dialog,
+            #[weak]
+            widget,
+            move |_| {
+                dialog.close();
+                share(&widget);
+            }
+        ));
+        group.add(&row);
+    };
+
+    add_target(
+        gettext("Copy Title and Link"),
+        "edit-copy-symbolic",
+        Box::new(glib::clone!(
+            #[strong]
+            text,
+            move |widget| copy_text(widget, &text, &gettext("Title and link copied"))
+        )),
+    );
+    let link = link.to_string();
+    add_target(
+        gettext("Copy Link"),
+        "insert-link-symbolic",
+        Box::new(move |widget| copy_text(widget, &link, &gettext("Link copied"))),
+    );
+    add_target(
+        gettext("Send by Email"),
+        "mail-send-symbolic",
+        Box::new(move |widget| send_by_email(widget, &mail_uri, &text)),
+    );
+
+    let toolbar_view = adw::ToolbarView::new();
+    toolbar_view.add_top_bar(&adw::HeaderBar::new());
+    toolbar_view.set_content(Some(
+        &adw::Clamp::builder()
+            .child(&group)
+            .margin_top(12)
+            .margin_bottom(24)
+            .margin_start(12)
+            .margin_end(12)
+            .build(),
+    ));
+    dialog.set_child(Some(&toolbar_view));
+    dialog.present(Some(&widget));
+}
+
+// if no mail app takes the link, the text gets copied instead
+fn send_by_email(widget: &gtk::Widget, mail_uri: &str, text: &str) {
+    let widget = widget.clone();
+    let text = text.to_string();
+    let window = widget.root().and_downcast::<gtk::Window>();
+    UriLauncher::new(mail_uri).launch(window.as_ref(), None::<&gio::Cancellable>, move |result| {
+        if let Err(e) = result {
+            println!("Could not share story: {}", e);
+            copy_text(&widget, &text, &gettext("Title and link copied"));
+        }
+    });
+}
+
+fn show_toast(widget: &impl IsA<gtk::Widget>, message: &str) {
+    if let Some(window) = widget.root().and_downcast::<GliderCloneWindow>() {
+        window.add_toast(adw::Toast::new(message));
+    }
+}
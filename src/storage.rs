@@ -0,0 +1,133 @@
+use std::{collections::HashMap, fs, path::PathBuf};
+
+use chrono::{Duration, Utc};
+use gtk::glib;
+use serde::{de::DeserializeOwned, Serialize};
+
+const APP_DIR_NAME: &str = "gtk-glider-clone";
+
+// the directory in $XDG_DATA_HOME where the app keeps its persistent state,
+// created on first use
+pub fn data_dir() -> PathBuf {
+    let dir = glib::user_data_dir().join(APP_DIR_NAME);
+
+    if let Err(e) = fs::create_dir_all(&dir) {
+        println!("Could not create data directory {}: {}", dir.display(), e);
+    }
+
+    dir
+}
+
+// the directory in $XDG_CACHE_HOME for data that can be fetched again, like API responses,
+// created on first use
+pub fn cache_dir() -> PathBuf {
+    let dir = glib::user_cache_dir().join(APP_DIR_NAME);
+
+    if let Err(e) = fs::create_dir_all(&dir) {
+        println!("Could not create cache directory {}: {}", dir.display(), e);
+    }
+
+    dir
+}
+
+// reads a JSON file from the data directory,
+// falling back to the default value if it is missing or cannot be parsed
+pub fn load_json<T: DeserializeOwned + Default>(file_name: &str) -> T {
+    let path = data_dir().join(file_name);
+
+    match fs::read(&path) {
+        Ok(bytes) => serde_json::from_slice(&bytes).unwrap_or_else(|e| {
+            println!("Could not parse {}: {}", path.display(), e);
+            T::default()
+        }),
+        Err(_) => T::default(),
+    }
+}
+
+// writes a value as JSON to the data directory,
+// going through a temporary file so a crash mid-write can't leave a truncated file behind
+pub fn save_json<T: Serialize>(file_name: &str, value: &T) {
+    let path = data_dir().join(file_name);
+    let tmp_path = path.with_extension("json.tmp");
+
+    let result = serde_json::to_vec(value)
+        .map_err(|e| e.to_string())
+        .and_then(|bytes| fs::write(&tmp_path, bytes).map_err(|e| e.to_string()))
+        .and_then(|_| fs::rename(&tmp_path, &path).map_err(|e| e.to_string()));
+
+    if let Err(e) = result {
+        println!("Could not save {}: {}", path.display(), e);
+    }
+}
+
+// item ids, each with the unix timestamp of when it was added, kept in a JSON file in the data directory
+// under a key naming what they are, like {"read": {"8863": 1700000000}}.
+// the reading history, hidden stories and bookmarks are each one of these
+pub struct TimestampedIds {
+    file_name: &'static str,
+    key: &'static str,
+    ids: HashMap<u32, i64>,
+}
+
+impl TimestampedIds {
+    // an empty list, which saving writes to `file_name`
+    pub fn new(file_name: &'static str, key: &'static str) -> Self {
+        TimestampedIds {
+            file_name,
+            key,
+            ids: HashMap::new(),
+        }
+    }
+
+    // reads the list from `file_name`, forgetting the ids added more than `max_age_days` ago if given,
+    // so lists that would otherwise only ever grow stay small
+    pub fn load(file_name: &'static str, key: &'static str, max_age_days: Option<i64>) -> Self {
+        let mut file: HashMap<String, HashMap<u32, i64>> = load_json(file_name);
+        let mut ids = file.remove(key).unwrap_or_default();
+        if let Some(max_age_days) = max_age_days {
+            prune_older_than(&mut ids, max_age_days, |added_at| *added_at);
+        }
+
+        TimestampedIds { file_name, key, ids }
+    }
+
+    pub fn save(&self) {
+        save_json(self.file_name, &HashMap::from([(self.key, &self.ids)]));
+    }
+
+    pub fn contains(&self, id: u32) -> bool {
+        self.ids.contains_key(&id)
+    }
+
+    pub fn insert(&mut self, id: u32) {
+        self.ids.insert(id, Utc::now().timestamp());
+    }
+
+    // returns whether the id was in the list
+    pub fn remove(&mut self, id: u32) -> bool {
+        self.ids.remove(&id).is_some()
+    }
+}
+
+// drops the entries keyed by item id whose `timestamp` is more than `max_age_days` ago
+pub fn prune_older_than<T>(entries: &mut HashMap<u32, T>, max_age_days: i64, timestamp: impl Fn(&T) -> i64) {
+    let cutoff = (Utc::now() - Duration::days(max_age_days)).timestamp();
+    entries.retain(|_, entry| timestamp(entry) >= cutoff);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn only_entries_past_the_max_age_are_pruned() {
+        let now = Utc::now().timestamp();
+        let mut entries = HashMap::from([(1, now), (2, now - 2 * 24 * 60 * 60), (3, now - 5 * 24 * 60 * 60)]);
+
+        prune_older_than(&mut entries, 3, |added_at| *added_at);
+
+        let mut kept: Vec<u32> = entries.into_keys().collect();
+        kept.sort();
+        assert_eq!(kept, vec![1, 2]);
+    }
+}
@@ -1,4 +1,7 @@
-use glib::subclass::{types::ObjectSubclass, InitializingObject};
+use glib::subclass::{
+    types::{ObjectSubclass, ObjectSubclassExt},
+    InitializingObject,
+};
 use gtk::glib::Object;
 use gtk::subclass::box_::BoxImpl;
 use gtk::subclass::widget::WidgetClassExt;
@@ -8,14 +11,44 @@ use gtk::subclass::{
 };
 use gtk::CompositeTemplate;
 use gtk::{
-    glib::{self},
-    prelude::ObjectExt,
+    accessible, gdk,
+    gio::{self, prelude::SettingsExt},
+    glib::{self, clone, ToVariant},
+    prelude::{AccessibleExt, BoxExt, Cast, GestureDragExt, GestureExt, ObjectExt, PopoverExt, WidgetExt},
     subclass::prelude::ObjectSubclassIsExt,
 };
-use gtk::{Label, TemplateChild};
-use std::cell::RefCell;
+use gtk::{
+    EventSequenceState, GestureClick, GestureDrag, GestureLongPress, Image, Label, Overlay, PopoverMenu,
+    TemplateChild,
+};
+use std::cell::{Cell, RefCell};
 
+use adw::prelude::AnimationExt;
+use chrono::Utc;
+use gettextrs::{gettext, ngettext};
+
+use crate::card_layout::{CardLayout, ScorePosition, SiteImage};
+use crate::favicon::load_favicon;
+use crate::feedback::haptic_cue;
+use crate::i18n::{fill, format_number};
+use crate::launcher::open_uri;
+use crate::network::website_item_url;
+use crate::relative_time::format_relative_time;
+use crate::settings::settings;
+use crate::share::{copy_text, share_story, ShareFormat};
 use crate::story_object::StoryObject;
+use crate::swipe_action::SwipeAction;
+use crate::thumbnail::load_thumbnail;
+use crate::transform::title_markup;
+
+// how far a drag has to go before it is taken to be a swipe or a scroll
+const DIRECTION_LOCK_DISTANCE: f64 = 12.0;
+// how far a card has to be swiped before letting go of it triggers the action
+const SWIPE_THRESHOLD: f64 = 96.0;
+// how long the card takes to slide back into place, in milliseconds
+const SNAP_BACK_DURATION: u32 = 200;
+// where the icon of stories without a link of their own comes from
+const HACKER_NEWS_HOST: &str = "news.ycombinator.com";
 
 glib::wrapper! {
     pub struct StoryCard(ObjectSubclass<imp::StoryCard>)
@@ -35,48 +68,152 @@ impl StoryCard {
         Object::builder().build()
     }
 
-    pub fn bind(&self, story_object: &StoryObject) {
-        let title_and_url_label = self.imp().title_and_url_label.get();
-        let score_count_label = self.imp().score_count_label.get();
-        let comments_count_label = self.imp().comments_count_label.get();
-        let author_label = self.imp().author_label.get();
-        let time_formatted_label = self.imp().time_formatted_label.get();
-        let mut bindings = self.imp().bindings.borrow_mut();
+    // builds the card for a story, with only the details the layout asks for
+    pub fn bind(&self, story_object: &StoryObject, layout: &CardLayout) {
+        self.set_placeholder(story_object.placeholder());
+        self.apply_layout(story_object, layout);
 
-        bindings.push(
-            story_object
-                .bind_property("title-and-url", &title_and_url_label, "label")
-                .sync_create()
-                .build(),
-        );
+        let imp = self.imp();
+        let mut bindings = imp.bindings.borrow_mut();
 
-        bindings.push(
-            story_object
-                .bind_property("score-count", &score_count_label, "label")
-                .sync_create()
-                .build(),
-        );
+        if layout.show_domain {
+            bindings.push(
+                story_object
+                    .bind_property("title-and-url", &imp.title_and_url_label.get(), "label")
+                    .sync_create()
+                    .build(),
+            );
+        } else {
+            bindings.push(
+                story_object
+                    .bind_property("title", &imp.title_and_url_label.get(), "label")
+                    .transform_to(|_, title: String| Some(title_markup(&title)))
+                    .sync_create()
+                    .build(),
+            );
+        }
 
-        bindings.push(
-            story_object
-                .bind_property("comments-count", &comments_count_label, "label")
-                .sync_create()
-                .build(),
-        );
+        if layout.show_rank {
+            bindings.push(
+                story_object
+                    .bind_property("rank", &imp.rank_label.get(), "label")
+                    .transform_to(|_, rank: u32| Some(format_number(rank)))
+                    .sync_create()
+                    .build(),
+            );
+        }
+
+        if layout.shows_score_badge() {
+            bindings.push(
+                story_object
+                    .bind_property("score-count", &imp.score_badge_label.get(), "label")
+                    .transform_to(|_, count: u32| Some(format_number(count)))
+                    .sync_create()
+                    .build(),
+            );
+        }
+
+        if layout.shows_inline_score() {
+            bindings.push(
+                story_object
+                    .bind_property("score-count", &imp.score_count_label.get(), "label")
+                    .transform_to(|_, count: u32| Some(format_number(count)))
+                    .sync_create()
+                    .build(),
+            );
+        }
 
+        if layout.show_comments {
+            bindings.push(
+                story_object
+                    .bind_property("comments-count", &imp.comments_count_label.get(), "label")
+                    .transform_to(|_, count: u32| Some(format_number(count)))
+                    .sync_create()
+                    .build(),
+            );
+
+            bindings.push(
+                story_object
+                    .bind_property("new-comments-count", &imp.new_comments_label.get(), "label")
+                    .transform_to(|_, count: u32| Some(fill(&gettext("{} new"), &[&format_number(count)])))
+                    .sync_create()
+                    .build(),
+            );
+        }
+
+        let show_comments = layout.show_comments;
         bindings.push(
             story_object
-                .bind_property("author", &author_label, "label")
+                .bind_property("new-comments-count", &imp.new_comments_label.get(), "visible")
+                .transform_to(move |_, count: u32| Some(show_comments && count > 0))
                 .sync_create()
                 .build(),
         );
 
         bindings.push(
             story_object
-                .bind_property("time-formatted", &time_formatted_label, "label")
+                .bind_property("bookmarked", &self.imp().bookmark_icon.get(), "visible")
                 .sync_create()
                 .build(),
         );
+
+        if layout.show_author {
+            bindings.push(
+                story_object
+                    .bind_property("author", &imp.author_label.get(), "label")
+                    .sync_create()
+                    .build(),
+            );
+        }
+
+        if layout.show_time {
+            bindings.push(
+                story_object
+                    .bind_property("time-formatted", &imp.time_formatted_label.get(), "label")
+                    .sync_create()
+                    .build(),
+            );
+        }
+
+        let mut handlers = imp.handlers.borrow_mut();
+
+        // dim the card whenever its story gets marked as read
+        self.set_style_class("read", story_object.read());
+        handlers.push(story_object.connect_read_notify(clone!(
+            #[weak(rename_to = story_card)]
+            self,
+            move |story_object| story_card.set_style_class("read", story_object.read())
+        )));
+
+        // highlight the card of the story open next to the feed
+        self.set_style_class("selected-story", story_object.selected());
+        handlers.push(story_object.connect_selected_notify(clone!(
+            #[weak(rename_to = story_card)]
+            self,
+            move |story_object| story_card.set_style_class("selected-story", story_object.selected())
+        )));
+
+        // the summary read out by screen readers follows whatever changes on the card
+        self.update_accessible_label(story_object);
+        handlers.push(story_object.connect_notify_local(
+            None,
+            clone!(
+                #[weak(rename_to = story_card)]
+                self,
+                move |story_object, _| story_card.update_accessible_label(story_object)
+            ),
+        ));
+
+        self.imp().story_object.replace(Some(story_object.clone()));
+        self.action_set_enabled("card.search-domain", !story_object.domain().is_empty());
+
+        if !story_object.placeholder() {
+            match layout.site_image {
+                SiteImage::Favicon => self.load_site_icon(story_object),
+                SiteImage::Thumbnail => self.load_site_thumbnail(story_object),
+                SiteImage::None => {}
+            }
+        }
     }
 
     pub fn unbind(&self) {
@@ -84,11 +221,431 @@ impl StoryCard {
         for binding in self.imp().bindings.borrow_mut().drain(..) {
             binding.unbind();
         }
+
+        let handlers: Vec<_> = self.imp().handlers.borrow_mut().drain(..).collect();
+        if let Some(story_object) = self.imp().story_object.take() {
+            for handler in handlers {
+                story_object.disconnect(handler);
+            }
+        }
+    }
+
+    // a placeholder card shows a shimmering skeleton instead of the story, and can't be interacted with
+    fn set_placeholder(&self, placeholder: bool) {
+        let imp = self.imp();
+        imp.skeleton.set_visible(placeholder);
+        imp.layout_box.set_visible(!placeholder);
+        self.set_can_target(!placeholder);
+        self.set_style_class("placeholder", placeholder);
+    }
+
+    // shows the parts of the card the layout asks for, and puts the score badge on its side
+    fn apply_layout(&self, story_object: &StoryObject, layout: &CardLayout) {
+        let imp = self.imp();
+        imp.rank_label.set_visible(layout.show_rank && story_object.rank() > 0);
+        imp.score_badge.set_visible(layout.shows_score_badge());
+        imp.score_icon.set_visible(layout.shows_inline_score());
+        imp.score_count_label.set_visible(layout.shows_inline_score());
+        imp.comments_icon.set_visible(layout.show_comments);
+        imp.comments_count_label.set_visible(layout.show_comments);
+        imp.author_label.set_visible(layout.show_author);
+        imp.time_formatted_label.set_visible(layout.show_time);
+
+        let badge_sibling = match layout.score_position {
+            ScorePosition::Right => imp.site_thumbnail.get().upcast::<gtk::Widget>(),
+            _ => imp.rank_label.get().upcast::<gtk::Widget>(),
+        };
+        imp.layout_box.reorder_child_after(&imp.score_badge.get(), Some(&badge_sibling));
+
+        // the site's initials stand in for its icon, Ask HN posts and the like belong to Hacker News itself
+        let site_name = if story_object.domain().is_empty() {
+            "Hacker News".to_string()
+        } else {
+            story_object.domain()
+        };
+        imp.site_icon.set_visible(layout.site_image == SiteImage::Favicon);
+        imp.site_icon.set_text(Some(&site_name));
+        imp.site_icon.set_custom_image(None::<&gdk::Paintable>);
+        // a thumbnail only takes up room once there is one, not every page has a preview
+        imp.site_thumbnail.set_visible(false);
+        imp.site_thumbnail.set_paintable(None::<&gdk::Paintable>);
+    }
+
+    // puts the icon of the story's site over the initials once it is in
+    fn load_site_icon(&self, story_object: &StoryObject) {
+        let host = if story_object.domain().is_empty() {
+            HACKER_NEWS_HOST.to_string()
+        } else {
+            story_object.domain()
+        };
+        let story_id = story_object.id();
+
+        load_favicon(
+            &host,
+            clone!(
+                #[weak(rename_to = story_card)]
+                self,
+                move |texture| {
+                    // the card may have been handed another story by the time the icon is in
+                    if story_card.story_object().is_some_and(|story_object| story_object.id() == story_id) {
+                        story_card.imp().site_icon.set_custom_image(Some(&texture));
+                    }
+                }
+            ),
+        );
+    }
+
+    // shows the preview image of the page the story links to at the end of the card once it is in
+    fn load_site_thumbnail(&self, story_object: &StoryObject) {
+        let story_id = story_object.id();
+
+        load_thumbnail(
+            &story_object.url(),
+            clone!(
+                #[weak(rename_to = story_card)]
+                self,
+                move |texture| {
+                    if story_card.story_object().is_some_and(|story_object| story_object.id() == story_id) {
+                        story_card.imp().site_thumbnail.set_paintable(Some(&texture));
+                        story_card.imp().site_thumbnail.set_visible(true);
+                    }
+                }
+            ),
+        );
+    }
+
+    // the `StoryObject` currently shown by this card, if any
+    pub fn story_object(&self) -> Option<StoryObject> {
+        self.imp().story_object.borrow().clone()
+    }
+
+    // everything the card shows, as one sentence, like
+    // "Story: title, example.com, 120 points, 45 comments, by user, 3 hours ago"
+    fn update_accessible_label(&self, story_object: &StoryObject) {
+        if story_object.placeholder() {
+            self.update_property(&[accessible::Property::Label(&gettext("Loading story"))]);
+            return;
+        }
+
+        let score_count = story_object.score_count();
+        let comments_count = story_object.comments_count();
+        let new_comments_count = story_object.new_comments_count();
+
+        let mut parts = vec![fill(&gettext("Story: {}"), &[&story_object.title()])];
+        if !story_object.domain().is_empty() {
+            parts.push(story_object.domain());
+        }
+        parts.push(fill(
+            &ngettext("{} point", "{} points", score_count),
+            &[&format_number(score_count)],
+        ));
+        parts.push(fill(
+            &ngettext("{} comment", "{} comments", comments_count),
+            &[&format_number(comments_count)],
+        ));
+        if new_comments_count > 0 {
+            parts.push(fill(
+                &ngettext("{} new comment", "{} new comments", new_comments_count),
+                &[&format_number(new_comments_count)],
+            ));
+        }
+        parts.push(fill(&gettext("by {}"), &[&story_object.author()]));
+        let time = format_relative_time(story_object.data().time, Utc::now());
+        if !time.is_empty() {
+            parts.push(time);
+        }
+        if story_object.bookmarked() {
+            parts.push(gettext("bookmarked"));
+        }
+        if story_object.read() {
+            parts.push(gettext("read"));
+        }
+
+        self.update_property(&[accessible::Property::Label(&parts.join(", "))]);
+    }
+
+    fn set_style_class(&self, css_class: &str, enabled: bool) {
+        if enabled {
+            self.add_css_class(css_class);
+        } else {
+            self.remove_css_class(css_class);
+        }
+    }
+
+    // lets the card be swiped sideways to trigger the actions chosen in the preferences
+    fn setup_swipe(&self) {
+        let swipe_overlay = self.imp().swipe_overlay.get();
+        let content = self.imp().content.get();
+        swipe_overlay.set_measure_overlay(&content, true);
+        swipe_overlay.set_clip_overlay(&content, true);
+
+        // the content slides over the background by however far it has been swiped
+        swipe_overlay.connect_get_child_position(clone!(
+            #[weak(rename_to = story_card)]
+            self,
+            #[upgrade_or]
+            None,
+            move |swipe_overlay, _| {
+                Some(gdk::Rectangle::new(
+                    story_card.imp().swipe_offset.get().round() as i32,
+                    0,
+                    swipe_overlay.width(),
+                    swipe_overlay.height(),
+                ))
+            }
+        ));
+
+        let gesture = GestureDrag::new();
+        gesture.connect_drag_update(clone!(
+            #[weak(rename_to = story_card)]
+            self,
+            move |gesture, offset_x, offset_y| {
+                if !story_card.imp().swiping.get() {
+                    if offset_x.abs() < DIRECTION_LOCK_DISTANCE && offset_y.abs() < DIRECTION_LOCK_DISTANCE {
+                        return;
+                    }
+
+                    // mostly vertical drags are left to the scrolled window, so scrolling the feed keeps working.
+                    // claiming the sequence for a sideways drag also stops it from activating the card
+                    if offset_x.abs() < offset_y.abs() * 2.0 {
+                        gesture.set_state(EventSequenceState::Denied);
+                        return;
+                    }
+                    gesture.set_state(EventSequenceState::Claimed);
+                    story_card.begin_swipe();
+                }
+
+                story_card.update_swipe(offset_x);
+            }
+        ));
+        gesture.connect_drag_end(clone!(
+            #[weak(rename_to = story_card)]
+            self,
+            move |_, _, _| story_card.end_swipe(true)
+        ));
+        gesture.connect_cancel(clone!(
+            #[weak(rename_to = story_card)]
+            self,
+            move |_, _| story_card.end_swipe(false)
+        ));
+        self.add_controller(gesture);
+    }
+
+    fn begin_swipe(&self) {
+        let imp = self.imp();
+        if let Some(animation) = imp.snap_back_animation.take() {
+            animation.skip();
+        }
+
+        // the actions are looked up on every swipe, so changes in the preferences apply right away
+        let settings = settings();
+        let right_action = SwipeAction::from_name(&settings.string("swipe-right-action"));
+        let left_action = SwipeAction::from_name(&settings.string("swipe-left-action"));
+        imp.right_action.set(right_action);
+        imp.left_action.set(left_action);
+        imp.start_action_icon.set_icon_name(Some(right_action.icon_name()));
+        imp.end_action_icon.set_icon_name(Some(left_action.icon_name()));
+
+        imp.swiping.set(true);
+        imp.armed.set(false);
+        imp.swipe_background.set_visible(true);
+        self.add_css_class("swiping");
+    }
+
+    fn update_swipe(&self, offset_x: f64) {
+        let imp = self.imp();
+        let action = self.swipe_action_for(offset_x);
+
+        // sides without an action don't move
+        let offset_x = if action == SwipeAction::None { 0.0 } else { offset_x };
+        self.set_swipe_offset(offset_x);
+
+        let swipe_background = imp.swipe_background.get();
+        swipe_background.set_css_classes(&["swipe-background", action.css_class()]);
+        imp.start_action_icon.set_visible(offset_x > 0.0);
+        imp.end_action_icon.set_visible(offset_x < 0.0);
+
+        // crossing the threshold is marked so the user knows letting go will do something
+        let armed = offset_x.abs() >= SWIPE_THRESHOLD;
+        if armed != imp.armed.get() {
+            imp.armed.set(armed);
+            self.set_style_class("swipe-armed", armed);
+            if armed {
+                haptic_cue();
+            }
+        }
+    }
+
+    // finishes a swipe, triggering its action if `commit` is set and the card was swiped far enough,
+    // and slides the card back into place
+    fn end_swipe(&self, commit: bool) {
+        let imp = self.imp();
+        if !imp.swiping.replace(false) {
+            return;
+        }
+
+        let offset_x = imp.swipe_offset.get();
+        let action = self.swipe_action_for(offset_x);
+        if commit && imp.armed.get() && action != SwipeAction::None {
+            let _ = self.activate_action(&format!("card.{}", action.name()), None);
+        }
+
+        let target = adw::CallbackAnimationTarget::new(clone!(
+            #[weak(rename_to = story_card)]
+            self,
+            move |offset_x| story_card.set_swipe_offset(offset_x)
+        ));
+        let animation = adw::TimedAnimation::new(self, offset_x, 0.0, SNAP_BACK_DURATION, target);
+        animation.connect_done(clone!(
+            #[weak(rename_to = story_card)]
+            self,
+            move |_| {
+                story_card.imp().swipe_background.set_visible(false);
+                story_card.set_style_class("swiping", false);
+                story_card.set_style_class("swipe-armed", false);
+            }
+        ));
+        animation.play();
+        imp.snap_back_animation.replace(Some(animation));
+    }
+
+    fn swipe_action_for(&self, offset_x: f64) -> SwipeAction {
+        if offset_x > 0.0 {
+            self.imp().right_action.get()
+        } else if offset_x < 0.0 {
+            self.imp().left_action.get()
+        } else {
+            SwipeAction::None
+        }
+    }
+
+    fn set_swipe_offset(&self, offset_x: f64) {
+        self.imp().swipe_offset.set(offset_x);
+        self.imp().swipe_overlay.queue_allocate();
+    }
+
+    // long-pressing or right-clicking a card opens a menu of quick actions for its story
+    fn setup_context_menu(&self) {
+        let context_menu = PopoverMenu::from_model(Some(&self.imp().card_menu.get()));
+        context_menu.set_parent(self);
+        self.imp().context_menu.replace(Some(context_menu));
+
+        // claiming the sequence keeps the list from also opening the story
+        let right_click = GestureClick::builder().button(gdk::BUTTON_SECONDARY).build();
+        right_click.connect_pressed(clone!(
+            #[weak(rename_to = story_card)]
+            self,
+            move |gesture, _, x, y| {
+                gesture.set_state(EventSequenceState::Claimed);
+                story_card.show_context_menu(x, y);
+            }
+        ));
+        self.add_controller(right_click);
+
+        let long_press = GestureLongPress::builder().touch_only(true).build();
+        long_press.connect_pressed(clone!(
+            #[weak(rename_to = story_card)]
+            self,
+            move |gesture, x, y| {
+                gesture.set_state(EventSequenceState::Claimed);
+                story_card.show_context_menu(x, y);
+            }
+        ));
+        self.add_controller(long_press);
+    }
+
+    fn show_context_menu(&self, x: f64, y: f64) {
+        if let Some(context_menu) = self.imp().context_menu.borrow().as_ref() {
+            context_menu.set_pointing_to(Some(&gdk::Rectangle::new(x as i32, y as i32, 1, 1)));
+            context_menu.popup();
+        }
+    }
+
+    // the menu opened from the keyboard points at the middle of the card
+    fn show_context_menu_from_keyboard(&self) -> glib::Propagation {
+        if self.story_object().is_some_and(|story_object| !story_object.placeholder()) {
+            self.show_context_menu(self.width() as f64 / 2.0, self.height() as f64 / 2.0);
+        }
+        glib::Propagation::Stop
+    }
+
+    // runs one of the feed page's actions on the card's story.
+    // the feed page isn't around for the cards in the filtered stories dialog, where nothing happens
+    fn activate_feed_action(&self, action_name: &str) {
+        if let Some(story_object) = self.story_object() {
+            let _ = self.activate_action(action_name, Some(&story_object.id().to_variant()));
+        }
+    }
+
+    // the article the story links to, or its discussion for stories without a link, like Ask HN posts
+    fn link(&self) -> Option<String> {
+        let story_object = self.story_object()?;
+        if story_object.url().is_empty() {
+            Some(website_item_url(story_object.id()))
+        } else {
+            Some(story_object.url())
+        }
+    }
+
+    fn open_link(&self) {
+        if let Some(link) = self.link() {
+            open_uri(self, &link);
+        }
+    }
+
+    fn share(&self) {
+        if let (Some(story_object), Some(link)) = (self.story_object(), self.link()) {
+            share_story(self, &story_object.title(), &link);
+        }
+    }
+
+    fn copy_link(&self) {
+        if let Some(link) = self.link() {
+            copy_text(self, &link, &gettext("Link copied"));
+        }
+    }
+
+    fn copy_hn_link(&self) {
+        if let Some(story_object) = self.story_object() {
+            copy_text(self, &website_item_url(story_object.id()), &gettext("Link copied"));
+        }
+    }
+
+    fn copy_title_and_link(&self) {
+        if let (Some(story_object), Some(link)) = (self.story_object(), self.link()) {
+            let text = ShareFormat::current().title_and_link(&story_object.title(), &link);
+            copy_text(self, &text, &gettext("Title and link copied"));
+        }
+    }
+
+    // the API is read-only, so votes have to be cast on the website where the user is logged in
+    fn upvote(&self) {
+        if let Some(story_object) = self.story_object() {
+            open_uri(self, &website_item_url(story_object.id()));
+        }
+    }
+
+    fn save_offline(&self) {
+        if let Some(story_object) = self.story_object() {
+            let _ = self.activate_action("app.save-offline", Some(&vec![story_object.id()].to_variant()));
+        }
+    }
+
+    fn view_author(&self) {
+        if let Some(story_object) = self.story_object() {
+            let _ = self.activate_action("app.open-user", Some(&story_object.author().to_variant()));
+        }
+    }
+
+    fn search_domain(&self) {
+        if let Some(story_object) = self.story_object() {
+            let _ = self.activate_action("app.open-site", Some(&story_object.domain().to_variant()));
+        }
     }
 }
 
 mod imp {
-    use glib::Binding;
+    use glib::{Binding, SignalHandlerId};
 
     use super::*;
 
@@ -97,17 +654,60 @@ mod imp {
     #[derive(CompositeTemplate, Default)]
     #[template(file = "src/ui/story_card.blp")]
     pub struct StoryCard {
+        #[template_child]
+        pub swipe_overlay: TemplateChild<Overlay>,
+        #[template_child]
+        pub swipe_background: TemplateChild<gtk::Box>,
+        #[template_child]
+        pub start_action_icon: TemplateChild<Image>,
+        #[template_child]
+        pub end_action_icon: TemplateChild<Image>,
+        #[template_child]
+        pub content: TemplateChild<gtk::Box>,
+        #[template_child]
+        pub skeleton: TemplateChild<gtk::Box>,
+        #[template_child]
+        pub layout_box: TemplateChild<gtk::Box>,
+        #[template_child]
+        pub rank_label: TemplateChild<Label>,
+        #[template_child]
+        pub score_badge: TemplateChild<gtk::Box>,
+        #[template_child]
+        pub score_badge_label: TemplateChild<Label>,
+        #[template_child]
+        pub site_icon: TemplateChild<adw::Avatar>,
         #[template_child]
         pub title_and_url_label: TemplateChild<Label>,
         #[template_child]
+        pub score_icon: TemplateChild<Image>,
+        #[template_child]
         pub score_count_label: TemplateChild<Label>,
         #[template_child]
+        pub comments_icon: TemplateChild<Image>,
+        #[template_child]
         pub comments_count_label: TemplateChild<Label>,
         #[template_child]
+        pub new_comments_label: TemplateChild<Label>,
+        #[template_child]
+        pub bookmark_icon: TemplateChild<Image>,
+        #[template_child]
         pub author_label: TemplateChild<Label>,
         #[template_child]
         pub time_formatted_label: TemplateChild<Label>,
+        #[template_child]
+        pub site_thumbnail: TemplateChild<Image>,
+        #[template_child]
+        pub card_menu: TemplateChild<gio::MenuModel>,
+        pub context_menu: RefCell<Option<PopoverMenu>>,
         pub bindings: RefCell<Vec<Binding>>,
+        pub story_object: RefCell<Option<StoryObject>>,
+        pub handlers: RefCell<Vec<SignalHandlerId>>,
+        pub swipe_offset: Cell<f64>,
+        pub swiping: Cell<bool>,
+        pub armed: Cell<bool>,
+        pub right_action: Cell<SwipeAction>,
+        pub left_action: Cell<SwipeAction>,
+        pub snap_back_animation: RefCell<Option<adw::TimedAnimation>>,
     }
 
     // The central trait for subclassing a GObject
@@ -120,6 +720,42 @@ mod imp {
 
         fn class_init(klass: &mut Self::Class) {
             klass.bind_template();
+
+            klass.install_action("card.open-link", None, |story_card, _, _| story_card.open_link());
+            klass.install_action("card.open-comments", None, |story_card, _, _| {
+                story_card.activate_feed_action("feed.open-comments");
+            });
+            klass.install_action("card.share", None, |story_card, _, _| story_card.share());
+            klass.install_action("card.copy-link", None, |story_card, _, _| story_card.copy_link());
+            klass.install_action("card.copy-hn-link", None, |story_card, _, _| story_card.copy_hn_link());
+            klass.install_action("card.copy-title-and-link", None, |story_card, _, _| {
+                story_card.copy_title_and_link();
+            });
+            klass.install_action("card.upvote", None, |story_card, _, _| story_card.upvote());
+            klass.install_action("card.bookmark", None, |story_card, _, _| {
+                story_card.activate_feed_action("feed.bookmark");
+            });
+            klass.install_action("card.hide", None, |story_card, _, _| {
+                story_card.activate_feed_action("feed.hide");
+            });
+            klass.install_action("card.mark-read", None, |story_card, _, _| {
+                story_card.activate_feed_action("feed.mark-read");
+            });
+            klass.install_action("card.save-offline", None, |story_card, _, _| story_card.save_offline());
+            klass.install_action("card.view-author", None, |story_card, _, _| story_card.view_author());
+            klass.install_action("card.search-domain", None, |story_card, _, _| story_card.search_domain());
+
+            // the quick actions are all in the card's menu, which the keyboard opens the usual way
+            klass.set_accessible_role(gtk::AccessibleRole::Article);
+            for key in [gdk::Key::Return, gdk::Key::KP_Enter] {
+                klass.add_binding_action(key, gdk::ModifierType::empty(), "card.open-link");
+            }
+            klass.add_binding(gdk::Key::Menu, gdk::ModifierType::empty(), |story_card| {
+                story_card.show_context_menu_from_keyboard()
+            });
+            klass.add_binding(gdk::Key::F10, gdk::ModifierType::SHIFT_MASK, |story_card| {
+                story_card.show_context_menu_from_keyboard()
+            });
         }
 
         fn instance_init(obj: &InitializingObject<Self>) {
@@ -136,7 +772,21 @@ mod imp {
             self.parent_constructed();
 
             // Setup
-            //let obj = self.obj();
+            let obj = self.obj();
+            obj.setup_swipe();
+            obj.setup_context_menu();
+            obj.update_property(&[
+                accessible::Property::KeyShortcuts("Enter C U B Menu Shift+F10"),
+                accessible::Property::Description(&gettext(
+                    "Press Enter to open the link, C for the comments, or the Menu key for actions like upvote, bookmark and share",
+                )),
+            ]);
+        }
+
+        fn dispose(&self) {
+            if let Some(context_menu) = self.context_menu.take() {
+                context_menu.unparent();
+            }
         }
     }
     // ANCHOR_END: constructed
@@ -1,12 +1,14 @@
 use glib::Object;
 use gtk::glib;
 
-use std::cell::RefCell;
+use std::cell::{Cell, RefCell};
 
 use glib::Properties;
 use gtk::prelude::*;
 use gtk::subclass::prelude::*;
 
+use crate::relative_time::time_label_markup;
+
 glib::wrapper! {
     pub struct StoryObject(ObjectSubclass<imp::StoryObject>);
 }
@@ -14,10 +16,16 @@ glib::wrapper! {
 #[derive(Clone, Debug, Default, glib::Boxed)]
 #[boxed_type(name = "CardData")]
 pub struct StoryData {
+    pub id: u32,
+    pub title: String,
+    pub domain: String,
+    pub url: String,
     pub title_and_url: String,
     pub score_count: u32,
     pub comments_count: u32,
     pub author: String,
+    // when the story was posted, as a unix timestamp
+    pub time: Option<i64>,
     pub time_formatted: String,
 }
 
@@ -27,6 +35,36 @@ impl StoryObject {
             .property("data", story_data)
             .build()
     }
+
+    // a stand-in for a story that is still being fetched, shown as a skeleton card
+    pub fn placeholder() -> Self {
+        Object::builder().property("placeholder", true).build()
+    }
+
+    // takes in a newer copy of the story, like one from a refresh of the feed,
+    // so the cards showing it pick up the new title, score and comment count
+    pub fn update(&self, story_data: StoryData) {
+        self.imp().data.replace(story_data);
+        for property in [
+            "data",
+            "title",
+            "domain",
+            "url",
+            "title-and-url",
+            "score-count",
+            "comments-count",
+            "author",
+            "time-formatted",
+        ] {
+            self.notify(property);
+        }
+    }
+
+    // "5 minutes ago" is only true for a minute, so the label is redone every so often
+    pub fn refresh_time(&self) {
+        let time = self.imp().data.borrow().time;
+        self.set_time_formatted(time_label_markup(time));
+    }
 }
 
 mod imp {
@@ -36,12 +74,30 @@ mod imp {
     #[properties(wrapper_type = super::StoryObject)]
     pub struct StoryObject {
         #[property(get, set, construct_only)]
+        #[property(name = "id", get, type = u32, member = id)]
+        #[property(name = "title", get, type = String, member = title)]
+        #[property(name = "domain", get, type = String, member = domain)]
+        #[property(name = "url", get, type = String, member = url)]
         #[property(name = "title-and-url", get, set, type = String, member = title_and_url)]
         #[property(name = "score-count", get, set, type = u32, member = score_count)]
         #[property(name = "comments-count", get, set, type = u32, member = comments_count)]
         #[property(name = "author", get, set, type = String, member = author)]
         #[property(name = "time-formatted", get, set, type = String, member = time_formatted)]
         pub data: RefCell<StoryData>,
+        #[property(get, set)]
+        pub read: Cell<bool>,
+        #[property(get, set)]
+        pub new_comments_count: Cell<u32>,
+        #[property(get, set)]
+        pub bookmarked: Cell<bool>,
+        // whether the story is the one open next to the feed
+        #[property(get, set)]
+        pub selected: Cell<bool>,
+        // where the story is in the feed it was fetched for, counting from 1
+        #[property(get, set)]
+        pub rank: Cell<u32>,
+        #[property(get, construct_only)]
+        pub placeholder: Cell<bool>,
     }
 
     // The central trait for subclassing a GObject
@@ -3,13 +3,35 @@ use glib::subclass::{
     types::{ObjectSubclass, ObjectSubclassExt},
     InitializingObject,
 };
+use gettextrs::gettext;
 use gtk::glib;
-use gtk::glib::Object;
+use gtk::glib::{clone, Object};
+use gtk::prelude::{AdjustmentExt, Cast, CastNone, FilterExt, ListItemExt, ListModelExt, WidgetExt};
+use gtk::subclass::prelude::ObjectSubclassIsExt;
+use gtk::subclass::widget::WidgetClassExt;
 use gtk::subclass::{
     prelude::{ObjectImpl, ObjectImplExt},
     widget::{CompositeTemplateClass, CompositeTemplateInitializingExt, WidgetImpl},
 };
-use gtk::CompositeTemplate;
+use gtk::{
+    gio::ListStore, CustomFilter, FilterChange, FilterListModel, ListItem, ListScrollFlags, ListView, NoSelection,
+    ScrolledWindow, SignalListItemFactory, Stack,
+};
+use gtk::{CompositeTemplate, TemplateChild};
+use std::cell::{Cell, RefCell};
+
+use adw::prelude::{AdwDialogExt, NavigationPageExt};
+
+use gtk::gio::prelude::SettingsExt;
+
+use crate::comment_object::{CommentData, CommentObject};
+use crate::comment_row::CommentRow;
+use crate::launcher::open_uri;
+use crate::network::website_item_url;
+use crate::seen_comments::SeenComments;
+use crate::settings::settings;
+use crate::share::{copy_text, share_story, ShareFormat};
+use crate::story_object::StoryData;
 
 glib::wrapper! {
     pub struct StoryPage(ObjectSubclass<imp::StoryPage>)
@@ -22,16 +44,420 @@ impl StoryPage {
     pub fn new() -> Self {
         Object::builder().build()
     }
+
+    fn comments(&self) -> ListStore {
+        self.imp()
+            .comments
+            .borrow()
+            .clone()
+            .expect("Could not get current comments.")
+    }
+
+    // the comments that are not folded away inside a collapsed thread, which is what the list shows
+    fn visible_comments(&self) -> FilterListModel {
+        self.imp()
+            .visible_comments
+            .borrow()
+            .clone()
+            .expect("Could not get visible comments.")
+    }
+
+    fn setup_model_and_view(&self) {
+        let model = ListStore::new::<CommentObject>();
+        self.imp().comments.replace(Some(model));
+
+        let filter = CustomFilter::new(|obj| {
+            let comment_object = obj
+                .downcast_ref::<CommentObject>()
+                .expect("The object needs to be of type `CommentObject`.");
+            !comment_object.hidden()
+        });
+        let filter_model = FilterListModel::new(Some(self.comments()), Some(filter.clone()));
+        self.imp().collapse_filter.replace(Some(filter));
+        self.imp().visible_comments.replace(Some(filter_model));
+
+        let selection_model = NoSelection::new(Some(self.visible_comments()));
+        self.imp().comments_list.set_model(Some(&selection_model));
+    }
+
+    fn setup_seen_comments(&self) {
+        let mut seen_comments = SeenComments::load();
+        seen_comments.prune(settings().uint("history-max-age") as i64);
+        seen_comments.save();
+        self.imp().seen_comments.replace(seen_comments);
+    }
+
+    // clears the page so the comments of another story can be loaded into it,
+    // optionally scrolling to one of them once they are in
+    pub fn load_story(&self, story_id: u32, focus_comment_id: Option<u32>) {
+        self.imp().story_id.set(story_id);
+        self.imp().focus_comment_id.set(focus_comment_id);
+        self.imp().next_new_position.set(0);
+        self.imp().collapsed_ids.borrow_mut().clear();
+        self.imp().pending_scroll_offset.set(None);
+        self.imp().url.replace(String::new());
+        self.comments().remove_all();
+        self.set_title("");
+        self.imp().content_stack.set_visible_child_name("comments");
+        self.action_set_enabled("story.next-new-comment", false);
+        self.action_set_enabled("story.open-article", false);
+        self.imp().saved_article.replace(None);
+        self.imp().saved_article_button.set_visible(false);
+        self.action_set_enabled("story.read-saved-article", false);
+        self.set_share_actions_enabled(false);
+    }
+
+    // makes the text of the page a story links to readable, when the story was saved for offline reading with it
+    pub fn set_saved_article(&self, story_id: u32, article: String) {
+        if story_id != self.imp().story_id.get() {
+            return;
+        }
+
+        self.imp().saved_article.replace(Some(article));
+        self.imp().saved_article_button.set_visible(true);
+        self.action_set_enabled("story.read-saved-article", true);
+    }
+
+    // shows the saved text of the article in a dialog, for when the page itself can't be opened
+    fn show_saved_article(&self) {
+        let Some(article) = self.imp().saved_article.borrow().clone() else {
+            return;
+        };
+
+        let article_label = gtk::Label::builder()
+            .label(article)
+            .xalign(0.0)
+            .wrap(true)
+            .wrap_mode(gtk::pango::WrapMode::WordChar)
+            .selectable(true)
+            .margin_top(12)
+            .margin_bottom(12)
+            .margin_start(12)
+            .margin_end(12)
+            .build();
+        let scrolled_window = ScrolledWindow::builder()
+            .hscrollbar_policy(gtk::PolicyType::Never)
+            .child(&adw::Clamp::builder().child(&article_label).build())
+            .build();
+
+        let toolbar_view = adw::ToolbarView::new();
+        toolbar_view.add_top_bar(&adw::HeaderBar::new());
+        toolbar_view.set_content(Some(&scrolled_window));
+
+        let dialog = adw::Dialog::builder()
+            .title(self.title())
+            .content_width(640)
+            .content_height(720)
+            .child(&toolbar_view)
+            .build();
+        dialog.present(Some(self));
+    }
+
+    // fills the page with the comments of the current story, highlighting the ones posted since the last visit,
+    // and returns whether the comments belonged to the current story
+    pub fn setup_comments(&self, story_data: StoryData, comment_data_vec: Vec<CommentData>) -> bool {
+        let story_id = story_data.id;
+
+        // the user may have moved on to another story while the comments were being fetched
+        if story_id != self.imp().story_id.get() {
+            return false;
+        }
+
+        self.set_title(&story_data.title);
+        self.action_set_enabled("story.open-article", !story_data.url.is_empty());
+        self.set_share_actions_enabled(true);
+        self.imp().url.replace(story_data.url);
+
+        let mut seen_comments = self.imp().seen_comments.borrow_mut();
+        let mut has_unseen = false;
+        let mut comment_ids: Vec<u32> = vec![];
+
+        let collapsed_ids = self.imp().collapsed_ids.borrow();
+
+        for comment_data in comment_data_vec {
+            let comment_object = CommentObject::new(comment_data);
+            let unseen = seen_comments.is_new(story_id, comment_object.id());
+            comment_object.set_unseen(unseen);
+            comment_object.set_collapsed(collapsed_ids.contains(&comment_object.id()));
+            has_unseen |= unseen;
+            comment_ids.push(comment_object.id());
+            self.comments().append(&comment_object);
+        }
+
+        seen_comments.record(story_id, comment_ids);
+        seen_comments.save();
+
+        self.action_set_enabled("story.next-new-comment", has_unseen);
+        drop(seen_comments);
+        drop(collapsed_ids);
+        self.update_hidden_comments();
+
+        if let Some(comment_id) = self.imp().focus_comment_id.take() {
+            self.scroll_to_comment(comment_id);
+        } else if let Some(offset) = self.imp().pending_scroll_offset.take() {
+            self.restore_scroll_offset(offset);
+        }
+        true
+    }
+
+    // folds a comment and its replies away, or unfolds them again
+    fn toggle_collapsed(&self, comment_id: u32) {
+        let comments = self.comments();
+        let comment_object = (0..comments.n_items())
+            .filter_map(|position| comments.item(position).and_downcast::<CommentObject>())
+            .find(|comment_object| comment_object.id() == comment_id);
+
+        if let Some(comment_object) = comment_object {
+            let collapsed = !comment_object.collapsed();
+            comment_object.set_collapsed(collapsed);
+
+            let mut collapsed_ids = self.imp().collapsed_ids.borrow_mut();
+            if collapsed {
+                collapsed_ids.push(comment_id);
+            } else {
+                collapsed_ids.retain(|id| *id != comment_id);
+            }
+            drop(collapsed_ids);
+
+            self.update_hidden_comments();
+        }
+    }
+
+    // the comments are stored depth-first, so the replies to a collapsed comment
+    // are all the comments after it up to the next one at the same depth or shallower
+    fn update_hidden_comments(&self) {
+        let comments = self.comments();
+        let mut collapsed_depth: Option<u32> = None;
+
+        for position in 0..comments.n_items() {
+            let comment_object = comments
+                .item(position)
+                .and_downcast::<CommentObject>()
+                .expect("The item has to be an `CommentObject`.");
+            let depth = comment_object.depth();
+
+            if collapsed_depth.is_some_and(|collapsed_depth| depth > collapsed_depth) {
+                comment_object.set_hidden(true);
+                continue;
+            }
+
+            comment_object.set_hidden(false);
+            collapsed_depth = comment_object.collapsed().then_some(depth);
+        }
+
+        if let Some(filter) = self.imp().collapse_filter.borrow().as_ref() {
+            filter.changed(FilterChange::Different);
+        }
+    }
+
+    pub fn refresh_times(&self) {
+        let comments = self.comments();
+        for position in 0..comments.n_items() {
+            if let Some(comment_object) = comments.item(position).and_downcast::<CommentObject>() {
+                comment_object.refresh_time();
+            }
+        }
+    }
+
+    pub fn story_id(&self) -> Option<u32> {
+        Some(self.imp().story_id.get()).filter(|story_id| *story_id != 0)
+    }
+
+    // the comments the user has collapsed in the current story, to be saved with the session
+    pub fn collapsed_ids(&self) -> Vec<u32> {
+        self.imp().collapsed_ids.borrow().clone()
+    }
+
+    pub fn scroll_offset(&self) -> f64 {
+        self.imp().comments_scrolled_window.vadjustment().value()
+    }
+
+    // reopens a story the way it was left in the previous session,
+    // the collapsed comments and scroll offset are applied once its comments arrive
+    pub fn restore_story(&self, story_id: u32, collapsed_ids: Vec<u32>, scroll_offset: f64) {
+        self.load_story(story_id, None);
+        self.imp().collapsed_ids.replace(collapsed_ids);
+        self.imp().pending_scroll_offset.set(Some(scroll_offset));
+    }
+
+    // the list only knows its full height once the rows have been laid out,
+    // so the offset is applied on the next idle rather than right away
+    fn restore_scroll_offset(&self, offset: f64) {
+        glib::idle_add_local_once(clone!(
+            #[weak(rename_to = story_page)]
+            self,
+            move || story_page.imp().comments_scrolled_window.vadjustment().set_value(offset)
+        ));
+    }
+
+    // scrolls to the next comment that is new since the last visit, wrapping around at the end of the thread
+    fn scroll_to_next_new_comment(&self) {
+        let comments = self.visible_comments();
+        let n_items = comments.n_items();
+        let start = self.imp().next_new_position.get();
+
+        let next_position = (0..n_items).map(|offset| (start + offset) % n_items).find(|position| {
+            comments
+                .item(*position)
+                .and_downcast::<CommentObject>()
+                .is_some_and(|comment_object| comment_object.unseen())
+        });
+
+        if let Some(position) = next_position {
+            self.imp()
+                .comments_list
+                .scroll_to(position, ListScrollFlags::FOCUS, None);
+            self.imp().next_new_position.set(position + 1);
+        }
+    }
+
+    // moves the keyboard focus to the next or previous comment that replies to the story itself,
+    // counting from the focused comment, or from the start of the thread when none has the focus
+    pub fn focus_top_level_comment(&self, forward: bool) {
+        let comments = self.visible_comments();
+        let focused_id = self
+            .imp()
+            .comments_list
+            .focus_child()
+            .and_then(|row| row.first_child())
+            .and_downcast::<CommentRow>()
+            .and_then(|comment_row| comment_row.comment_object())
+            .map(|comment_object| comment_object.id());
+        let focused_position = focused_id.and_then(|comment_id| {
+            (0..comments.n_items()).find(|position| {
+                comments
+                    .item(*position)
+                    .and_downcast::<CommentObject>()
+                    .is_some_and(|comment_object| comment_object.id() == comment_id)
+            })
+        });
+
+        let is_top_level = |position: &u32| {
+            comments
+                .item(*position)
+                .and_downcast::<CommentObject>()
+                .is_some_and(|comment_object| comment_object.depth() == 0)
+        };
+        let position = match (focused_position, forward) {
+            (Some(focused_position), true) => (focused_position + 1..comments.n_items()).find(is_top_level),
+            (Some(focused_position), false) => (0..focused_position).rev().find(is_top_level),
+            (None, _) => (0..comments.n_items()).find(is_top_level),
+        };
+
+        if let Some(position) = position {
+            self.imp()
+                .comments_list
+                .scroll_to(position, ListScrollFlags::FOCUS, None);
+        }
+    }
+
+    fn set_share_actions_enabled(&self, enabled: bool) {
+        for action_name in ["story.share", "story.copy-link", "story.copy-hn-link", "story.copy-title-and-link"] {
+            self.action_set_enabled(action_name, enabled);
+        }
+    }
+
+    // the article the story links to, or its discussion for stories without a link
+    fn link(&self) -> String {
+        let url = self.imp().url.borrow();
+        if url.is_empty() {
+            website_item_url(self.imp().story_id.get())
+        } else {
+            url.clone()
+        }
+    }
+
+    // scrolls to the comment with the given id, used to get from a reply to its parent
+    fn scroll_to_comment(&self, comment_id: u32) {
+        let comments = self.visible_comments();
+        let position = (0..comments.n_items()).find(|position| {
+            comments
+                .item(*position)
+                .and_downcast::<CommentObject>()
+                .is_some_and(|comment_object| comment_object.id() == comment_id)
+        });
+
+        if let Some(position) = position {
+            self.imp()
+                .comments_list
+                .scroll_to(position, ListScrollFlags::FOCUS, None);
+        }
+    }
+
+    fn setup_factory(&self) {
+        let factory = SignalListItemFactory::new();
+
+        factory.connect_setup(move |_, list_item| {
+            let comment_row = CommentRow::new();
+            let list_item = list_item.downcast_ref::<ListItem>().expect("Needs to be ListItem");
+            // focus goes to the comment itself, like with the story cards
+            list_item.set_focusable(false);
+            list_item.set_child(Some(&comment_row));
+        });
+
+        factory.connect_bind(move |_, list_item| {
+            let comment_object = list_item
+                .downcast_ref::<ListItem>()
+                .expect("Needs to be ListItem")
+                .item()
+                .and_downcast::<CommentObject>()
+                .expect("The item has to be an `CommentObject`.");
+
+            let comment_row = list_item
+                .downcast_ref::<ListItem>()
+                .expect("Needs to be ListItem")
+                .child()
+                .and_downcast::<CommentRow>()
+                .expect("The child has to be a `CommentRow`.");
+
+            comment_row.bind(&comment_object);
+        });
+
+        factory.connect_unbind(move |_, list_item| {
+            let comment_row = list_item
+                .downcast_ref::<ListItem>()
+                .expect("Needs to be ListItem")
+                .child()
+                .and_downcast::<CommentRow>()
+                .expect("The child has to be a `CommentRow`.");
+
+            comment_row.unbind();
+        });
+
+        self.imp().comments_list.set_factory(Some(&factory));
+    }
 }
 
 mod imp {
+    use glib::VariantTy;
+
     use super::*;
 
     // ANCHOR: struct_and_subclass
     // Object holding the state
     #[derive(CompositeTemplate, Default)]
     #[template(file = "src/ui/story_page.blp")]
-    pub struct StoryPage { }
+    pub struct StoryPage {
+        #[template_child]
+        pub content_stack: TemplateChild<Stack>,
+        #[template_child]
+        pub comments_scrolled_window: TemplateChild<ScrolledWindow>,
+        #[template_child]
+        pub comments_list: TemplateChild<ListView>,
+        #[template_child]
+        pub saved_article_button: TemplateChild<gtk::Button>,
+        pub comments: RefCell<Option<ListStore>>,
+        pub visible_comments: RefCell<Option<FilterListModel>>,
+        pub collapse_filter: RefCell<Option<CustomFilter>>,
+        pub collapsed_ids: RefCell<Vec<u32>>,
+        pub pending_scroll_offset: Cell<Option<f64>>,
+        pub seen_comments: RefCell<SeenComments>,
+        pub story_id: Cell<u32>,
+        pub focus_comment_id: Cell<Option<u32>>,
+        pub next_new_position: Cell<u32>,
+        pub url: RefCell<String>,
+        pub saved_article: RefCell<Option<String>>,
+    }
 
     // The central trait for subclassing a GObject
     #[glib::object_subclass]
@@ -43,6 +469,41 @@ mod imp {
 
         fn class_init(klass: &mut Self::Class) {
             klass.bind_template();
+
+            klass.install_action("story.next-new-comment", None, |story_page, _, _| {
+                story_page.scroll_to_next_new_comment();
+            });
+            klass.install_action("story.open-article", None, |story_page, _, _| {
+                open_uri(story_page, &story_page.imp().url.borrow());
+            });
+            klass.install_action("story.read-saved-article", None, |story_page, _, _| {
+                story_page.show_saved_article();
+            });
+            klass.install_action("story.share", None, |story_page, _, _| {
+                share_story(story_page, &story_page.title(), &story_page.link());
+            });
+            klass.install_action("story.copy-link", None, |story_page, _, _| {
+                copy_text(story_page, &story_page.link(), &gettext("Link copied"));
+            });
+            klass.install_action("story.copy-hn-link", None, |story_page, _, _| {
+                let link = website_item_url(story_page.imp().story_id.get());
+                copy_text(story_page, &link, &gettext("Link copied"));
+            });
+            klass.install_action("story.copy-title-and-link", None, |story_page, _, _| {
+                let text = ShareFormat::current().title_and_link(&story_page.title(), &story_page.link());
+                copy_text(story_page, &text, &gettext("Title and link copied"));
+            });
+            klass.install_action("story.toggle-collapsed", Some(VariantTy::UINT32), |story_page, _, parameter| {
+                if let Some(comment_id) = parameter.and_then(|parameter| parameter.get::<u32>()) {
+                    story_page.toggle_collapsed(comment_id);
+                }
+            });
+            // activated by the comment rows with the id of the comment to show
+            klass.install_action("story.view-comment", Some(VariantTy::UINT32), |story_page, _, parameter| {
+                if let Some(comment_id) = parameter.and_then(|parameter| parameter.get::<u32>()) {
+                    story_page.scroll_to_comment(comment_id);
+                }
+            });
         }
 
         fn instance_init(obj: &InitializingObject<Self>) {
@@ -59,7 +520,14 @@ mod imp {
             self.parent_constructed();
 
             // Setup
-            let _obj = self.obj();
+            let obj = self.obj();
+            obj.setup_seen_comments();
+            obj.setup_model_and_view();
+            obj.setup_factory();
+            obj.action_set_enabled("story.next-new-comment", false);
+            obj.action_set_enabled("story.open-article", false);
+            obj.action_set_enabled("story.read-saved-article", false);
+            obj.set_share_actions_enabled(false);
         }
     }
     // ANCHOR_END: constructed
@@ -0,0 +1,61 @@
+// what swiping a story card to either side does, as chosen in the preferences
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum SwipeAction {
+    #[default]
+    None,
+    Upvote,
+    Bookmark,
+    Hide,
+    MarkRead,
+    Share,
+}
+
+impl SwipeAction {
+    pub fn from_name(name: &str) -> Self {
+        match name {
+            "upvote" => SwipeAction::Upvote,
+            "bookmark" => SwipeAction::Bookmark,
+            "hide" => SwipeAction::Hide,
+            "mark-read" => SwipeAction::MarkRead,
+            "share" => SwipeAction::Share,
+            _ => SwipeAction::None,
+        }
+    }
+
+    // the value used for the action in the settings,
+    // which is also the name of the story card action it triggers
+    pub fn name(&self) -> &'static str {
+        match self {
+            SwipeAction::None => "none",
+            SwipeAction::Upvote => "upvote",
+            SwipeAction::Bookmark => "bookmark",
+            SwipeAction::Hide => "hide",
+            SwipeAction::MarkRead => "mark-read",
+            SwipeAction::Share => "share",
+        }
+    }
+
+    // the icon revealed behind the card while swiping
+    pub fn icon_name(&self) -> &'static str {
+        match self {
+            SwipeAction::None => "",
+            SwipeAction::Upvote => "arrow2-up-symbolic",
+            SwipeAction::Bookmark => "starred-symbolic",
+            SwipeAction::Hide => "view-conceal-symbolic",
+            SwipeAction::MarkRead => "object-select-symbolic",
+            SwipeAction::Share => "send-to-symbolic",
+        }
+    }
+
+    // the style class giving the revealed background its color
+    pub fn css_class(&self) -> &'static str {
+        match self {
+            SwipeAction::None => "swipe-none",
+            SwipeAction::Upvote => "swipe-upvote",
+            SwipeAction::Bookmark => "swipe-bookmark",
+            SwipeAction::Hide => "swipe-hide",
+            SwipeAction::MarkRead => "swipe-mark-read",
+            SwipeAction::Share => "swipe-share",
+        }
+    }
+}
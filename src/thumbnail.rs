@@ -0,0 +1,341 @@
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex, MutexGuard, OnceLock};
+use std::time::{Duration, SystemTime};
+
+use gtk::{gdk, gdk_pixbuf, gio, glib};
+use reqwest::{header::CONTENT_TYPE, Client, Response};
+use tokio::sync::OnceCell;
+use url::Url;
+
+use crate::data_usage::{record_download, transfer_size, DataCategory};
+use crate::favicon::image_client;
+use crate::html::decode_entities;
+use crate::http_cache::fnv1a;
+use crate::network::{read_body, FetchError};
+use crate::storage::cache_dir;
+use crate::transform::runtime;
+
+// the tags a page names its preview image in, for link previews on social media, the first one found wins
+const THUMBNAIL_PROPERTIES: [&str; 4] = ["og:image", "og:image:url", "og:image:secure_url", "twitter:image"];
+// the tags are in the head of the page, which comes first, so the rest of a long page isn't downloaded
+const MAX_HEAD_SIZE: usize = 512 * 1024;
+// previews are made to be shown big, but anything bigger than this is too much for a tile on a card
+const MAX_IMAGE_SIZE: usize = 2 * 1024 * 1024;
+// the side of the square the preview is cut down to, twice the size it is shown at for high resolution screens
+const THUMBNAIL_SIZE: i32 = 128;
+const THUMBNAIL_TIMEOUT: Duration = Duration::from_secs(10);
+// pages rarely change their preview once they are up, so the one on disk is used for a month
+const THUMBNAIL_MAX_AGE: Duration = Duration::from_secs(30 * 24 * 60 * 60);
+
+// the thumbnail of every page asked for so far, as a PNG, or `None` for the pages that don't have one.
+// works the same as the favicons, a page being fetched has an empty cell the cards wait on,
+// which stays empty if fetching failed in a way that may well go away
+type Thumbnail = Arc<OnceCell<Option<glib::Bytes>>>;
+
+fn thumbnails() -> MutexGuard<'static, HashMap<String, Thumbnail>> {
+    static THUMBNAILS: OnceLock<Mutex<HashMap<String, Thumbnail>>> = OnceLock::new();
+    THUMBNAILS
+        .get_or_init(|| Mutex::new(HashMap::new()))
+        .lock()
+        .expect("The thumbnail lock should not be poisoned.")
+}
+
+thread_local! {
+    // the thumbnails turned into textures so far, on the GTK thread they can't leave
+    static TEXTURES: RefCell<HashMap<String, gdk::Texture>> = RefCell::default();
+}
+
+// hands the preview image of the page a story links to to `on_loaded` on the GTK thread, cut to a square.
+// it is the image the page names for link previews, or the link itself if that is an image.
+// like the favicons, it is read from disk or downloaded on the tokio runtime, and only downloaded
+// while not saving data. pages without a preview, and stories without a link, get nothing
+pub fn load_thumbnail(url: &str, on_loaded: impl FnOnce(gdk::Texture) + 'static) {
+    if !url.starts_with("https://") && !url.starts_with("http://") {
+        return;
+    }
+
+    if let Some(texture) = TEXTURES.with_borrow(|textures| textures.get(url).cloned()) {
+        on_loaded(texture);
+        return;
+    }
+
+    let thumbnail = thumbnails().entry(url.to_string()).or_default().clone();
+    if thumbnail.get().is_some_and(Option::is_none) {
+        return;
+    }
+
+    let url = url.to_string();
+    let client = image_client();
+    glib::spawn_future_local(async move {
+        let image = runtime().spawn(fetch_thumbnail(url.clone(), thumbnail, client)).await;
+        if let Ok(Some(image)) = image {
+            if let Ok(texture) = gdk::Texture::from_bytes(&image) {
+                TEXTURES.with_borrow_mut(|textures| textures.insert(url, texture.clone()));
+                on_loaded(texture);
+            }
+        }
+    });
+}
+
+async fn fetch_thumbnail(url: String, thumbnail: Thumbnail, client: Option<&'static Client>) -> Option<glib::Bytes> {
+    if let Some(image) = thumbnail.get() {
+        return image.clone();
+    }
+    if let Some(image) = read_thumbnail(&url, client.is_some()).await {
+        let _ = thumbnail.set(Some(image.clone()));
+        return Some(image);
+    }
+    let client = client?;
+
+    let image = thumbnail
+        .get_or_try_init(|| async {
+            match download_thumbnail(client, &url).await {
+                Ok(image) => Ok(Some(image)),
+                Err(e) if e.is_transient() => Err(e),
+                Err(_) => Ok(None),
+            }
+        })
+        .await;
+    image.ok().cloned().flatten()
+}
+
+// most pages have a preview, the ones that don't, or that aren't pages at all, aren't worth reporting
+async fn download_thumbnail(client: &Client, url: &str) -> Result<glib::Bytes, FetchError> {
+    let response = client.get(url).timeout(THUMBNAIL_TIMEOUT).send().await?.error_for_status()?;
+
+    let content_type = content_type(&response);
+    let image = if content_type.starts_with("image/") {
+        read_body(response, MAX_IMAGE_SIZE, DataCategory::Images).await?
+    } else if content_type.contains("html") {
+        let page_url = response.url().clone();
+        let head = read_head(response).await?;
+        let image_url = find_thumbnail_url(&head, &page_url)
+            .ok_or_else(|| FetchError::Rejected(format!("{} names no preview image", url)))?;
+
+        let response = client.get(image_url).timeout(THUMBNAIL_TIMEOUT).send().await?.error_for_status()?;
+        read_body(response, MAX_IMAGE_SIZE, DataCategory::Images).await?
+    } else {
+        return Err(FetchError::Rejected(format!("{} is neither a page nor an image but {}", url, content_type)));
+    };
+
+    // decoding and scaling a big image takes a while, so it is done off the runtime's threads
+    let thumbnail = runtime()
+        .spawn_blocking(move || square_thumbnail(&image))
+        .await
+        .ok()
+        .flatten()
+        .ok_or_else(|| FetchError::Rejected(format!("The preview of {} is not an image", url)))?;
+
+    if let Err(e) = tokio::fs::write(thumbnail_path(url), &thumbnail).await {
+        println!("Could not cache thumbnail of {}: {}", url, e);
+    }
+
+    Ok(glib::Bytes::from_owned(thumbnail))
+}
+
+fn content_type(response: &Response) -> String {
+    response
+        .headers()
+        .get(CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok())
+        .unwrap_or_default()
+        .to_ascii_lowercase()
+}
+
+// reads a page up to the end of its head, or as much of it as a head could possibly take up
+async fn read_head(mut response: Response) -> Result<String, FetchError> {
+    let headers = response.headers().clone();
+    let mut head: Vec<u8> = vec![];
+    let result: Result<(), FetchError> = loop {
+        match response.chunk().await {
+            Ok(Some(chunk)) => {
+                // the end of the head may be split between this chunk and the one before
+                let searched = head.len().saturating_sub("</head>".len());
+                head.extend_from_slice(&chunk);
+                let ended = head[searched..].windows("</head>".len()).any(|tag| tag.eq_ignore_ascii_case(b"</head>"));
+                if ended || head.len() >= MAX_HEAD_SIZE {
+                    break Ok(());
+                }
+            }
+            Ok(None) => break Ok(()),
+            Err(e) => break Err(e.into()),
+        }
+    };
+    record_download(DataCategory::Images, transfer_size(&headers, head.len()));
+    result?;
+
+    Ok(String::from_utf8_lossy(&head).into_owned())
+}
+
+// the preview image a page names in its meta tags, like
+// <meta property="og:image" content="/preview.png">, made absolute against where the page is
+fn find_thumbnail_url(html: &str, page_url: &Url) -> Option<Url> {
+    // lowercasing only touches ASCII letters, so positions in one are the same in the other
+    let lowercase = html.to_ascii_lowercase();
+    let mut images: Vec<(String, String)> = vec![];
+    let mut position = 0;
+
+    while let Some(start) = lowercase[position..].find("<meta") {
+        let start = position + start + "<meta".len();
+        let end = lowercase[start..].find('>').map_or(html.len(), |end| start + end);
+        position = end;
+
+        let attributes = attributes(html[start..end].trim_end_matches('/'));
+        let property = attributes
+            .iter()
+            .find(|(name, _)| name == "property" || name == "name")
+            .map(|(_, value)| value.to_ascii_lowercase());
+        let content = attributes.iter().find(|(name, _)| name == "content").map(|(_, value)| value);
+        if let (Some(property), Some(content)) = (property, content) {
+            images.push((property, decode_entities(content.trim())));
+        }
+    }
+
+    THUMBNAIL_PROPERTIES
+        .iter()
+        .filter_map(|property| images.iter().find(|(name, _)| name == property))
+        .filter(|(_, content)| !content.is_empty())
+        .filter_map(|(_, content)| page_url.join(content).ok())
+        .find(|image_url| image_url.scheme() == "https" || image_url.scheme() == "http")
+}
+
+// the attributes of a tag, with lowercase names, values in double, single or no quotes, or no value at all
+fn attributes(tag: &str) -> Vec<(String, &str)> {
+    let mut attributes = vec![];
+    let mut rest = tag.trim_start();
+
+    while !rest.is_empty() {
+        let name_end = rest
+            .find(|character: char| character == '=' || character.is_whitespace())
+            .unwrap_or(rest.len());
+        if name_end == 0 {
+            // a stray "=" without a name before it
+            rest = rest[1..].trim_start();
+            continue;
+        }
+        let name = rest[..name_end].to_ascii_lowercase();
+        rest = rest[name_end..].trim_start();
+
+        let value = match rest.strip_prefix('=').map(str::trim_start) {
+            Some(quoted) if quoted.starts_with(['"', '\'']) => {
+                let quote = quoted.as_bytes()[0] as char;
+                let end = quoted[1..].find(quote).map_or(quoted.len(), |end| end + 1);
+                rest = quoted.get(end + 1..).unwrap_or_default();
+                &quoted[1..end]
+            }
+            Some(unquoted) => {
+                let end = unquoted.find(char::is_whitespace).unwrap_or(unquoted.len());
+                rest = &unquoted[end..];
+                &unquoted[..end]
+            }
+            None => "",
+        };
+        attributes.push((name, value));
+        rest = rest.trim_start();
+    }
+
+    attributes
+}
+
+// cuts the middle square out of an image and scales it down, like a picture set to cover its tile,
+// handing it back as a PNG, which is all that is kept of it
+fn square_thumbnail(image: &[u8]) -> Option<Vec<u8>> {
+    let stream = gio::MemoryInputStream::from_bytes(&glib::Bytes::from(image));
+    let pixbuf = gdk_pixbuf::Pixbuf::from_stream(&stream, None::<&gio::Cancellable>).ok()?;
+
+    let side = pixbuf.width().min(pixbuf.height());
+    let square = pixbuf.new_subpixbuf((pixbuf.width() - side) / 2, (pixbuf.height() - side) / 2, side, side);
+    let scaled = square.scale_simple(
+        THUMBNAIL_SIZE.min(side),
+        THUMBNAIL_SIZE.min(side),
+        gdk_pixbuf::InterpType::Bilinear,
+    )?;
+
+    scaled.save_to_bufferv("png", &[]).ok()
+}
+
+// a thumbnail past its age is only used while no newer one may be downloaded
+async fn read_thumbnail(url: &str, download: bool) -> Option<glib::Bytes> {
+    let path = thumbnail_path(url);
+    let age = tokio::fs::metadata(&path)
+        .await
+        .and_then(|metadata| metadata.modified())
+        .ok()
+        .and_then(|modified| SystemTime::now().duration_since(modified).ok())?;
+    if download && age > THUMBNAIL_MAX_AGE {
+        return None;
+    }
+
+    tokio::fs::read(path).await.ok().map(glib::Bytes::from_owned)
+}
+
+fn thumbnails_dir() -> &'static Path {
+    static THUMBNAILS_DIR: OnceLock<PathBuf> = OnceLock::new();
+    THUMBNAILS_DIR.get_or_init(|| {
+        let dir = cache_dir().join("thumbnails");
+
+        if let Err(e) = fs::create_dir_all(&dir) {
+            println!("Could not create cache directory {}: {}", dir.display(), e);
+        }
+
+        dir
+    })
+}
+
+// URLs can be longer than a file name may be, so thumbnails are stored under a hash of the story's link
+fn thumbnail_path(url: &str) -> PathBuf {
+    thumbnails_dir().join(format!("{:016x}.png", fnv1a(url.as_bytes())))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn page_url() -> Url {
+        Url::parse("https://example.com/posts/1").unwrap()
+    }
+
+    #[test]
+    fn open_graph_image_is_found() {
+        let html = r#"<html><head><title>Post</title>
+            <meta name="twitter:image" content="https://cdn.example.com/twitter.png">
+            <META content='https://cdn.example.com/og.png?a=1&amp;b=2' PROPERTY="og:image" />
+            </head>"#;
+        assert_eq!(
+            find_thumbnail_url(html, &page_url()).map(String::from),
+            Some("https://cdn.example.com/og.png?a=1&b=2".to_string())
+        );
+    }
+
+    #[test]
+    fn relative_images_are_made_absolute() {
+        let html = r#"<meta property=og:image content=/images/preview.jpg>"#;
+        assert_eq!(
+            find_thumbnail_url(html, &page_url()).map(String::from),
+            Some("https://example.com/images/preview.jpg".to_string())
+        );
+    }
+
+    #[test]
+    fn pages_without_a_web_image_have_none() {
+        assert_eq!(find_thumbnail_url(r#"<meta name="description" content="a post">"#, &page_url()), None);
+        assert_eq!(find_thumbnail_url(r#"<meta property="og:image" content="data:image/png,x">"#, &page_url()), None);
+        assert_eq!(find_thumbnail_url(r#"<meta property="og:image" content="#, &page_url()), None);
+    }
+
+    #[test]
+    fn attributes_are_split_up() {
+        assert_eq!(
+            attributes(r#" property="og:title" content='A "quoted" title' hidden data-x=1"#),
+            vec![
+                ("property".to_string(), "og:title"),
+                ("content".to_string(), r#"A "quoted" title"#),
+                ("hidden".to_string(), ""),
+                ("data-x".to_string(), "1"),
+            ]
+        );
+    }
+}
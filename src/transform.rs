@@ -1,16 +1,28 @@
 use std::sync::OnceLock;
 
 use async_channel::Sender;
-use chrono::{DateTime, Utc};
-use gtk::glib::{clone, markup_escape_text};
+use gettextrs::gettext;
+use futures::StreamExt;
+use gtk::glib::{self, clone, markup_escape_text};
 use reqwest::Client;
-use tokio::runtime::Runtime;
+use tokio::{
+    runtime::Runtime,
+    task::{spawn_blocking, AbortHandle},
+};
 use url::Url;
 
 use crate::{
     application::Event,
-    network::{fetch_stories, Item},
+    comment_object::CommentData,
+    html::html_to_markup,
+    network::{
+        fetch_article, fetch_comments, fetch_item, fetch_items, fetch_root_story, fetch_source_ids, fetch_user,
+        FeedSource, FetchError, Item, User,
+    },
+    offline_store::{extract_article_text, load_article, load_thread, save_thread, SavedThread},
+    relative_time::time_label_markup,
     story_object::StoryData,
+    user_page::UserData,
 };
 
 // https://gtk-rs.org/gtk4-rs/stable/latest/book/main_event_loop.html#tokio
@@ -20,93 +32,324 @@ pub fn runtime() -> &'static Runtime {
     RUNTIME.get_or_init(|| Runtime::new().expect("Setting up tokio runtime needs to succeed."))
 }
 
-// spawns a Tokio runtime that uses reqwest to fetch stories,
-// maps them to the Item model and returns these in a vector,
-// then tranforms these into a vector of CardData, which is Item data that has been processed for putting into Card widgets,
-// and finally sends them in a message on the Vec<CardData> async channel to be received by the watcher at an indeterminate point
-pub fn spawn_cards_fetch_and_send(sender: &Sender<Event>, client: &Client) {
+// spawns a Tokio runtime that uses reqwest to fetch the ids of the stories a source lists,
+// then fetches the stories themselves and transforms each into StoryData, which is Item data that has been processed for putting into Card widgets.
+// every story is sent on the async channel as soon as it and the ones ranked above it are in,
+// so the first cards show up after a single round-trip rather than once the whole batch is in.
+// every event carries the batch number it was started with, so the receiver can tell apart a superseded batch
+pub fn spawn_cards_fetch_and_send(
+    sender: &Sender<Event>,
+    client: &Client,
+    source: FeedSource,
+    count: usize,
+    batch: u32,
+) -> AbortHandle {
+    runtime().spawn(clone!(
+        #[strong]
+        sender,
+        #[strong]
+        client,
+        async move {
+            // a site or search may well have no stories, and nothing can be loaded while offline,
+            // so an empty batch is passed on rather than treated as fatal
+            let mut failures: usize = 0;
+            let story_ids: Vec<u32> = match fetch_source_ids(&client, &source, count).await {
+                Ok(ids) => ids.into_iter().take(count).collect(),
+                Err(e) => {
+                    println!("{}", e);
+                    if e.is_failure() {
+                        failures += 1;
+                    }
+                    vec![]
+                }
+            };
+
+            sender
+                .send(Event::StartedStories(batch, story_ids.len()))
+                .await
+                .expect("The channel needs to be open.");
+
+            let mut loaded_any = false;
+            let mut story_items = fetch_items(&client, story_ids);
+            while let Some(result) = story_items.next().await {
+                match result {
+                    Ok(story_item) => {
+                        let story_data: StoryData = story_to_card_data(story_item);
+                        loaded_any = true;
+
+                        sender
+                            .send(Event::SentStory(batch, story_data))
+                            .await
+                            .expect("The channel needs to be open.");
+                    }
+                    Err(e) => {
+                        println!("{}", e);
+                        if e.is_failure() {
+                            failures += 1;
+                        }
+                    }
+                }
+            }
+
+            // an empty batch with nothing failing is just an empty site or search
+            let event = if !loaded_any && failures > 0 {
+                Event::FailedStories(batch, failures)
+            } else {
+                Event::FinishedStories(batch, failures)
+            };
+            sender.send(event).await.expect("The channel needs to be open.");
+        }
+    ))
+    .abort_handle()
+}
+
+// fetches a story together with its whole comment tree,
+// transforms the comments into CommentData and sends them on the async channel,
+// followed by the text of its article if the story was saved for offline reading
+pub fn spawn_comments_fetch_and_send(sender: &Sender<Event>, client: &Client, story_id: u32) -> AbortHandle {
     runtime().spawn(clone!(
         #[strong]
         sender,
         #[strong]
         client,
         async move {
-            let stories_result = fetch_stories(&client).await;
-            let mut story_items: Vec<Item> = vec![];
+            // a story saved for offline reading is still readable when it can't be fetched
+            // the saved files are read on a blocking thread, they can be large
+            let (story_item, comment_items, article) = match fetch_item(&client, story_id).await {
+                Ok(item) => {
+                    let comment_items = fetch_comments(&client, &item).await;
+                    let article = spawn_blocking(move || load_article(story_id)).await.ok().flatten();
+                    (item, comment_items, article)
+                }
+                Err(e) => match spawn_blocking(move || load_thread(story_id)).await.ok().flatten() {
+                    Some(thread) => (thread.story, thread.comments, thread.article),
+                    None => {
+                        println!("{}", e);
+                        return;
+                    }
+                },
+            };
+
+            let comment_data_vec: Vec<CommentData> = comments_to_comment_data_transform(comment_items);
+            let story_data: StoryData = story_to_card_data(story_item);
+
+            sender
+                .send(Event::SentComments(story_data, comment_data_vec))
+                .await
+                .expect("The channel needs to be open.");
 
-            match stories_result {
-                Ok(items) => story_items = items,
-                Err(e) => println!("{}", e),
+            if let Some(article) = article {
+                sender
+                    .send(Event::SentArticle(story_id, article))
+                    .await
+                    .expect("The channel needs to be open.");
             }
+        }
+    ))
+    .abort_handle()
+}
+
+// saves stories for offline reading one after the other, each with its whole comment tree
+// and the text of the page it links to, sending the progress of the batch on the async channel
+pub fn spawn_offline_save_and_send(sender: &Sender<Event>, client: &Client, story_ids: Vec<u32>) {
+    runtime().spawn(clone!(
+        #[strong]
+        sender,
+        #[strong]
+        client,
+        async move {
+            let total = story_ids.len();
+            let mut failures: usize = 0;
+
+            for (done, story_id) in story_ids.into_iter().enumerate() {
+                match fetch_thread(&client, story_id).await {
+                    Ok(thread) => {
+                        let thread = spawn_blocking(move || {
+                            save_thread(&thread);
+                            thread
+                        })
+                        .await
+                        .expect("Saving a thread does not panic.");
+                        let domain = story_to_card_data(thread.story.clone()).domain;
 
-            let card_data_vec: Vec<StoryData> = stories_to_card_data_transform(story_items);
+                        sender
+                            .send(Event::SavedOffline(thread.saved_story(domain)))
+                            .await
+                            .expect("The channel needs to be open.");
+                    }
+                    Err(e) => {
+                        println!("Could not save {} for offline reading: {}", story_id, e);
+                        failures += 1;
+                    }
+                }
 
-            if card_data_vec.is_empty() {
-                panic!("Failed to load any stories");
+                sender
+                    .send(Event::OfflineProgress(done + 1, total))
+                    .await
+                    .expect("The channel needs to be open.");
             }
 
             sender
-                .send(Event::SentStoryData(card_data_vec))
+                .send(Event::FinishedOffline(total - failures, failures))
                 .await
                 .expect("The channel needs to be open.");
         }
     ));
 }
 
-// process JSON data from the Hacker News API into presentable strings tailored for Card widgets
-pub fn stories_to_card_data_transform(story_items: Vec<Item>) -> Vec<StoryData> {
-    let mut story_data: Vec<StoryData> = vec![];
-    story_items.into_iter().for_each(|story_item| {
-        let mut url: String = "".to_string();
-
-        if story_item.url.is_some() {
-            let parsed_url = Url::parse(story_item.url.clone().unwrap().as_str());
-
-            if parsed_url.is_ok() && parsed_url.unwrap().host_str().is_some() {
-                url = Url::parse(story_item.url.unwrap().as_str())
-                    .unwrap()
-                    .host_str()
-                    .unwrap()
-                    .to_string();
+// a page that can't be fetched or has no text to speak of doesn't keep its story's comments from being saved
+async fn fetch_thread(client: &Client, story_id: u32) -> Result<SavedThread, FetchError> {
+    let story = fetch_item(client, story_id).await?;
+    let comments = fetch_comments(client, &story).await;
+
+    let article = match &story.url {
+        Some(url) => match fetch_article(client, url).await {
+            Ok(html) => extract_article_text(&html),
+            Err(e) => {
+                println!("Could not fetch the article of {}: {}", story_id, e);
+                None
             }
+        },
+        None => None,
+    };
+
+    Ok(SavedThread {
+        story,
+        comments,
+        article,
+    })
+}
+
+// finds the story an item belongs to, so links to single comments open their whole thread,
+// and sends its id on the async channel together with the id of the linked comment
+pub fn spawn_item_resolve_and_send(sender: &Sender<Event>, client: &Client, id: u32) {
+    runtime().spawn(clone!(
+        #[strong]
+        sender,
+        #[strong]
+        client,
+        async move {
+            let (story_item, comment_id) = match fetch_root_story(&client, id).await {
+                Ok(resolved) => resolved,
+                Err(e) => {
+                    println!("{}", e);
+                    return;
+                }
+            };
+
+            sender
+                .send(Event::ResolvedItem(story_item.id, comment_id))
+                .await
+                .expect("The channel needs to be open.");
+        }
+    ));
+}
+
+// fetches a user's profile, transforms it into UserData and sends it on the async channel
+pub fn spawn_user_fetch_and_send(sender: &Sender<Event>, client: &Client, user_id: String) {
+    runtime().spawn(clone!(
+        #[strong]
+        sender,
+        #[strong]
+        client,
+        async move {
+            let user = match fetch_user(&client, &user_id).await {
+                Ok(user) => user,
+                Err(e) => {
+                    println!("{}", e);
+                    return;
+                }
+            };
+
+            sender
+                .send(Event::SentUser(user_to_user_data_transform(user)))
+                .await
+                .expect("The channel needs to be open.");
         }
+    ));
+}
+
+// the title of a story the way its card shows it, a little bigger than the details under it
+pub fn title_markup(title: &str) -> String {
+    format!("<span size=\"115%\">{}</span>", markup_escape_text(title))
+}
+
+// process JSON data from the Hacker News API into presentable strings tailored for Card widgets
+pub fn story_to_card_data(story_item: Item) -> StoryData {
+    let mut domain: String = "".to_string();
 
-        let title_and_url: String = format!(
-            "<span size=\"115%\">{}</span> <span foreground=\"grey\">({})</span>",
-            markup_escape_text(story_item.title.unwrap_or("".to_string()).as_str()),
-            url
-        );
-
-        let mut time_string: String = "".to_string();
-        let date_time = DateTime::from_timestamp(story_item.time.unwrap_or(0), 0);
-        let time_difference = Utc::now() - date_time.unwrap();
-
-        if time_difference.num_days() == 0 {
-            time_string = format!("{} hours ago", time_difference.num_hours());
-        } else if time_difference.num_days() > 0 && time_difference.num_weeks() == 0 {
-            time_string = format!("{} days ago", time_difference.num_days());
-        } else if time_difference.num_weeks() > 0 && time_difference.num_weeks() < 4 {
-            time_string = format!("{} weeks ago", time_difference.num_weeks());
-        } else if time_difference.num_weeks() >= 4 && time_difference.num_weeks() < 52 {
-            let num_months = ((time_difference.num_weeks() / 4) as f32).round() as u32;
-            time_string = format!("{} months ago", num_months);
-        } else if time_difference.num_weeks() >= 52 {
-            let num_years: u32 = ((time_difference.num_weeks() / 52) as f32).round() as u32;
-            time_string = format!("{} years ago", num_years);
+    if story_item.url.is_some() {
+        let parsed_url = Url::parse(story_item.url.clone().unwrap().as_str());
+
+        if parsed_url.is_ok() && parsed_url.unwrap().host_str().is_some() {
+            domain = Url::parse(story_item.url.clone().unwrap().as_str())
+                .unwrap()
+                .host_str()
+                .unwrap()
+                .to_string();
         }
+    }
+
+    let url: String = story_item.url.unwrap_or("".to_string());
+
+    let title: String = story_item.title.unwrap_or("".to_string());
+
+    let title_and_url: String = format!("{} <span foreground=\"grey\">({})</span>", title_markup(&title), domain);
+
+    let time_formatted: String = time_label_markup(story_item.time);
+
+    StoryData {
+        id: story_item.id,
+        title,
+        domain,
+        url,
+        title_and_url,
+        score_count: story_item.score.unwrap_or(0),
+        comments_count: story_item.descendants.unwrap_or(0),
+        author: story_item.by.unwrap_or("".to_string()),
+        time: story_item.time,
+        time_formatted,
+    }
+}
+
+// process comment items from the Hacker News API into presentable strings tailored for CommentRow widgets
+pub fn comments_to_comment_data_transform(comment_items: Vec<(Item, u32)>) -> Vec<CommentData> {
+    comment_items
+        .into_iter()
+        .map(|(comment_item, depth)| {
+            let text: String = if comment_item.deleted.unwrap_or(false) {
+                gettext("[deleted]")
+            } else if comment_item.dead.unwrap_or(false) {
+                gettext("[dead]")
+            } else {
+                html_to_markup(comment_item.text.unwrap_or("".to_string()).as_str())
+            };
+
+            CommentData {
+                id: comment_item.id,
+                author: comment_item.by.unwrap_or("".to_string()),
+                time: comment_item.time,
+                time_formatted: time_label_markup(comment_item.time),
+                text,
+                depth,
+                parent: comment_item.parent.unwrap_or(0),
+            }
+        })
+        .collect()
+}
+
+// process a user from the Hacker News API into presentable strings tailored for the UserPage
+pub fn user_to_user_data_transform(user: User) -> UserData {
+    let created_formatted = glib::DateTime::from_unix_local(user.created)
+        // Translators: the date a user joined, see the GLib documentation of g_date_time_format
+        .and_then(|created| created.format(&gettext("%B %-d, %Y")))
+        .map(|created| created.to_string())
+        .unwrap_or_default();
 
-        let time_formatted: String = format!(
-            "<span foreground=\"grey\">{}</span>",
-            markup_escape_text(time_string.as_str())
-        );
-
-        story_data.push(StoryData {
-            title_and_url,
-            score_count: story_item.score.unwrap_or(0),
-            comments_count: story_item.descendants.unwrap_or(0),
-            author: story_item.by.unwrap_or("".to_string()),
-            time_formatted,
-        });
-    });
-    story_data
+    UserData {
+        id: user.id,
+        karma: user.karma,
+        created_formatted,
+        about: html_to_markup(user.about.unwrap_or("".to_string()).as_str()),
+    }
 }
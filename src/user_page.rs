@@ -0,0 +1,134 @@
+use adw::subclass::prelude::NavigationPageImpl;
+use glib::subclass::{types::ObjectSubclass, InitializingObject};
+use gtk::glib;
+use gtk::glib::Object;
+use gtk::prelude::WidgetExt;
+use gtk::subclass::prelude::ObjectSubclassIsExt;
+use gtk::subclass::widget::WidgetClassExt;
+use gtk::subclass::{
+    prelude::{ObjectImpl, ObjectImplExt},
+    widget::{CompositeTemplateClass, CompositeTemplateInitializingExt, WidgetImpl},
+};
+use gtk::{CompositeTemplate, Label, TemplateChild};
+use std::cell::RefCell;
+
+use adw::prelude::{ActionRowExt, NavigationPageExt, PreferencesRowExt};
+
+use crate::i18n::format_number;
+use crate::launcher::open_uri;
+use crate::network::website_user_url;
+
+// a user's profile, processed for showing on the UserPage
+#[derive(Clone, Debug, Default)]
+pub struct UserData {
+    pub id: String,
+    pub karma: u32,
+    pub created_formatted: String,
+    pub about: String,
+}
+
+glib::wrapper! {
+    pub struct UserPage(ObjectSubclass<imp::UserPage>)
+        @extends adw::NavigationPage, gtk::Widget,
+        @implements gtk::Accessible, gtk::Buildable,
+                    gtk::ConstraintTarget;
+}
+
+impl Default for UserPage {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl UserPage {
+    pub fn new() -> Self {
+        Object::builder().build()
+    }
+
+    pub fn user_id(&self) -> String {
+        self.imp().user_id.borrow().clone()
+    }
+
+    // clears the page so the profile of another user can be loaded into it
+    pub fn load_user(&self, user_id: &str) {
+        let imp = self.imp();
+        imp.user_id.replace(user_id.to_string());
+        self.set_title(user_id);
+        imp.karma_row.set_subtitle("");
+        imp.created_row.set_subtitle("");
+        imp.about_label.set_label("");
+        imp.about_label.set_visible(false);
+    }
+
+    // fills in the profile, and returns whether it belonged to the user currently shown
+    pub fn setup_user(&self, user_data: UserData) -> bool {
+        let imp = self.imp();
+
+        // the user may have moved on to another profile while this one was being fetched
+        if user_data.id != *imp.user_id.borrow() {
+            return false;
+        }
+
+        imp.karma_row.set_subtitle(&format_number(user_data.karma));
+        imp.created_row.set_subtitle(&user_data.created_formatted);
+        imp.about_label.set_visible(!user_data.about.is_empty());
+        imp.about_label.set_label(&user_data.about);
+        true
+    }
+}
+
+mod imp {
+    use super::*;
+
+    // ANCHOR: struct_and_subclass
+    // Object holding the state
+    #[derive(CompositeTemplate, Default)]
+    #[template(file = "src/ui/user_page.blp")]
+    pub struct UserPage {
+        #[template_child]
+        pub karma_row: TemplateChild<adw::ActionRow>,
+        #[template_child]
+        pub created_row: TemplateChild<adw::ActionRow>,
+        #[template_child]
+        pub about_label: TemplateChild<Label>,
+        pub user_id: RefCell<String>,
+    }
+
+    // The central trait for subclassing a GObject
+    #[glib::object_subclass]
+    impl ObjectSubclass for UserPage {
+        // `NAME` needs to match `class` attribute of template
+        const NAME: &'static str = "UserPage";
+        type Type = super::UserPage;
+        type ParentType = adw::NavigationPage;
+
+        fn class_init(klass: &mut Self::Class) {
+            klass.bind_template();
+
+            klass.install_action("user.open-website", None, |user_page, _, _| {
+                open_uri(user_page, &website_user_url(&user_page.imp().user_id.borrow()));
+            });
+        }
+
+        fn instance_init(obj: &InitializingObject<Self>) {
+            obj.init_template();
+        }
+    }
+    // ANCHOR_END: struct_and_subclass
+
+    // ANCHOR: constructed
+    // Trait shared by all GObjects
+    impl ObjectImpl for UserPage {
+        fn constructed(&self) {
+            // Call "constructed" on parent
+            self.parent_constructed();
+        }
+    }
+    // ANCHOR_END: constructed
+
+    // Trait shared by all widgets
+    impl WidgetImpl for UserPage {}
+
+    // Trait shared by all NavigationPages
+    impl NavigationPageImpl for UserPage {}
+}
@@ -1,12 +1,37 @@
+use adw::prelude::{AdwDialogExt, NavigationPageExt};
 use adw::subclass::prelude::AdwApplicationWindowImpl;
 use adw::NavigationPage;
+use adw::NavigationSplitView;
 use adw::NavigationView;
 use adw::Application;
+use gettextrs::{gettext, ngettext};
 use glib::subclass::InitializingObject;
 use glib::Object;
 use gtk::subclass::prelude::*;
 use gtk::CompositeTemplate;
-use gtk::{gio, glib, subclass::prelude::ObjectSubclassIsExt};
+use gtk::prelude::{ActionMapExt, ActionMapExtManual, Cast, CastNone, GtkWindowExt, WidgetExt};
+use gtk::{gio, glib, glib::clone, subclass::prelude::ObjectSubclassIsExt};
+use std::cell::{OnceCell, RefCell};
+
+use crate::feed_page::FeedPage;
+use crate::i18n::{fill, format_number};
+use crate::launcher::open_uri;
+use crate::network::website_item_url;
+use crate::preferences_dialog::GliderPreferencesDialog;
+use crate::story_page::StoryPage;
+
+// the keyboard shortcuts are single keys, so they are turned off while a text field has the focus
+const SINGLE_KEY_ACTIONS: [&str; 9] = [
+    "next-story",
+    "previous-story",
+    "open-link",
+    "open-comments",
+    "upvote",
+    "bookmark",
+    "next-comment",
+    "previous-comment",
+    "go-back",
+];
 
 glib::wrapper! {
     pub struct GliderCloneWindow(ObjectSubclass<imp::GliderCloneWindow>)
@@ -21,12 +46,254 @@ impl GliderCloneWindow {
         Object::builder().property("application", app).build()
     }
 
-    pub fn add_nav_page(&self, nav_page: &NavigationPage) {
-        self.imp().nav_view.add(nav_page);
+    // the feed goes in the sidebar, which is the root page when the layout is collapsed
+    pub fn set_feed_page(&self, feed_page: &FeedPage) {
+        self.imp().split_view.set_sidebar(Some(feed_page));
+        if self.imp().feed_page.set(feed_page.clone()).is_err() {
+            println!("The feed page can only be set once");
+        }
+    }
+
+    pub fn set_story_page(&self, story_page: &StoryPage) {
+        self.imp().split_view.set_content(Some(story_page));
+        if self.imp().story_page.set(story_page.clone()).is_err() {
+            println!("The story page can only be set once");
+        }
+    }
+
+    // the app-wide settings, whose change handlers are connected when the app starts up.
+    // they are kept here since the window lives as long as the handlers are needed
+    pub fn set_settings(&self, settings: gio::Settings) {
+        if self.imp().settings.set(settings).is_err() {
+            println!("The settings can only be set once");
+        }
+    }
+
+    pub fn settings(&self) -> &gio::Settings {
+        self.imp().settings.get().expect("The settings need to be set.")
+    }
+
+    fn feed_page(&self) -> &FeedPage {
+        self.imp().feed_page.get().expect("The feed page needs to be set.")
+    }
+
+    fn story_page(&self) -> &StoryPage {
+        self.imp().story_page.get().expect("The story page needs to be set.")
     }
 
+    // brings the story page into view, which only needs navigating to when the layout is collapsed.
+    // the split view remembers this across resizes, so the open story stays put
+    pub fn show_story_page(&self) {
+        self.imp().nav_view.pop_to_tag("main");
+        self.imp().split_view.set_show_content(true);
+    }
+
+    pub fn show_feed_page(&self) {
+        self.imp().nav_view.pop_to_tag("main");
+        self.imp().split_view.set_show_content(false);
+    }
+
+    // pushes a page over the feed and comments, unless it is already the one on top
     pub fn push_nav_page(&self, nav_page: &NavigationPage) {
-        self.imp().nav_view.push(nav_page);
+        let nav_view = self.imp().nav_view.get();
+        if nav_view.visible_page().as_ref() == Some(nav_page) {
+            return;
+        }
+
+        nav_view.pop_to_tag("main");
+        nav_view.push(nav_page);
+    }
+
+    // whether the story page is the one in view when the layout is collapsed
+    pub fn is_story_page_shown(&self) -> bool {
+        self.imp().split_view.shows_content()
+    }
+
+    // the tag of the page on top of the navigation stack, "main" unless another page was pushed over it
+    pub fn visible_page_tag(&self) -> Option<glib::GString> {
+        self.imp().nav_view.visible_page().and_then(|page| page.tag())
+    }
+
+    pub fn add_toast(&self, toast: adw::Toast) {
+        self.imp().toast_overlay.add_toast(toast);
+    }
+
+    // keeps a single toast up to date with how far saving stories for offline reading has got,
+    // bringing it back if the user dismissed it while the batch was still going
+    pub fn show_offline_progress(&self, done: usize, total: usize) {
+        let title = fill(
+            // Translators: {0} is the number of stories saved so far, {1} the number being saved
+            &gettext("Saving for offline… {0} of {1}"),
+            &[&format_number(done as u32), &format_number(total as u32)],
+        );
+
+        let mut progress_toast = self.imp().offline_progress_toast.borrow_mut();
+        match progress_toast.as_ref() {
+            Some(toast) => toast.set_title(&title),
+            None => {
+                let toast = adw::Toast::builder().title(title).timeout(0).build();
+                toast.connect_dismissed(clone!(
+                    #[weak(rename_to = window)]
+                    self,
+                    move |_| {
+                        window.imp().offline_progress_toast.replace(None);
+                    }
+                ));
+                self.add_toast(toast.clone());
+                progress_toast.replace(toast);
+            }
+        }
+    }
+
+    // replaces the progress toast with the outcome of the batch
+    pub fn finish_offline_progress(&self, saved: usize, failures: usize) {
+        if let Some(toast) = self.imp().offline_progress_toast.take() {
+            toast.dismiss();
+        }
+
+        // the plural form follows the last number in both sentences
+        let title = if failures == 0 {
+            fill(
+                &ngettext("Saved {} story for offline reading", "Saved {} stories for offline reading", saved as u32),
+                &[&format_number(saved as u32)],
+            )
+        } else {
+            let total = saved + failures;
+            fill(
+                &ngettext(
+                    // Translators: {0} is the number of stories saved, {1} the number there were to save
+                    "Saved {0} of {1} story for offline reading",
+                    "Saved {0} of {1} stories for offline reading",
+                    total as u32,
+                ),
+                &[&format_number(saved as u32), &format_number(total as u32)],
+            )
+        };
+        let toast = adw::Toast::builder()
+            .title(title)
+            .button_label(gettext("Show"))
+            .action_name("app.show-offline")
+            .build();
+        self.add_toast(toast);
+    }
+
+    // opens the preferences, at the mute filters if asked for
+    fn show_preferences(&self, show_mute_filters: bool) {
+        let dialog = GliderPreferencesDialog::new();
+        // the feed sorts its stories again as soon as a filter is added or removed
+        dialog.connect_mute_filters_changed(clone!(
+            #[weak(rename_to = window)]
+            self,
+            move |_| window.feed_page().reload_mute_filters()
+        ));
+        if show_mute_filters {
+            dialog.show_mute_filters();
+        }
+        dialog.present(Some(self));
+    }
+
+    // the actions behind the keyboard shortcuts, which `App` gives their keys
+    fn setup_actions(&self) {
+        let next_story = gio::ActionEntry::builder("next-story")
+            .activate(|window: &Self, _, _| window.focus_story(true))
+            .build();
+        let previous_story = gio::ActionEntry::builder("previous-story")
+            .activate(|window: &Self, _, _| window.focus_story(false))
+            .build();
+        let open_link = gio::ActionEntry::builder("open-link")
+            .activate(|window: &Self, _, _| window.open_link())
+            .build();
+        let open_comments = gio::ActionEntry::builder("open-comments")
+            .activate(|window: &Self, _, _| {
+                if let Some(story_object) = window.feed_page().current_story() {
+                    window.feed_page().open_comments(&story_object);
+                }
+            })
+            .build();
+        let upvote = gio::ActionEntry::builder("upvote")
+            .activate(|window: &Self, _, _| window.upvote())
+            .build();
+        let bookmark = gio::ActionEntry::builder("bookmark")
+            .activate(|window: &Self, _, _| {
+                if let Some(story_object) = window.feed_page().current_story() {
+                    window.feed_page().toggle_bookmark(&story_object);
+                }
+            })
+            .build();
+        let next_comment = gio::ActionEntry::builder("next-comment")
+            .activate(|window: &Self, _, _| window.focus_top_level_comment(true))
+            .build();
+        let previous_comment = gio::ActionEntry::builder("previous-comment")
+            .activate(|window: &Self, _, _| window.focus_top_level_comment(false))
+            .build();
+        let go_back = gio::ActionEntry::builder("go-back")
+            .activate(|window: &Self, _, _| window.go_back())
+            .build();
+        self.add_action_entries([
+            next_story,
+            previous_story,
+            open_link,
+            open_comments,
+            upvote,
+            bookmark,
+            next_comment,
+            previous_comment,
+            go_back,
+        ]);
+
+        self.connect_focus_widget_notify(|window| {
+            let typing = window.focus().is_some_and(|widget| widget.is::<gtk::Editable>());
+            for action_name in SINGLE_KEY_ACTIONS {
+                if let Some(action) = window.lookup_action(action_name).and_downcast::<gio::SimpleAction>() {
+                    action.set_enabled(!typing);
+                }
+            }
+        });
+    }
+
+    // moving between stories on a phone brings the feed back into view first
+    fn focus_story(&self, forward: bool) {
+        if self.imp().split_view.is_collapsed() && self.is_story_page_shown() {
+            self.show_feed_page();
+        }
+        self.feed_page().focus_next_story(forward);
+    }
+
+    fn focus_top_level_comment(&self, forward: bool) {
+        if self.story_page().story_id().is_some() {
+            self.show_story_page();
+            self.story_page().focus_top_level_comment(forward);
+        }
+    }
+
+    // stories opened from a link aren't in the feed, the story page opens their article instead
+    fn open_link(&self) {
+        match self.feed_page().current_story() {
+            Some(story_object) => self.feed_page().open_link(&story_object),
+            None => {
+                let _ = self.story_page().activate_action("story.open-article", None);
+            }
+        }
+    }
+
+    // opens the story on the website to vote there, like the upvote of the card menu
+    fn upvote(&self) {
+        let story_id = match self.feed_page().current_story() {
+            Some(story_object) => Some(story_object.id()),
+            None => self.story_page().story_id(),
+        };
+        if let Some(story_id) = story_id {
+            open_uri(self, &website_item_url(story_id));
+        }
+    }
+
+    // closes a page pushed over the feed and comments, or goes from the comments back to the feed on a phone
+    fn go_back(&self) {
+        if self.visible_page_tag().as_deref() != Some("main") {
+            self.imp().nav_view.pop();
+        } else if self.imp().split_view.is_collapsed() && self.is_story_page_shown() {
+            self.show_feed_page();
+        }
     }
 }
 
@@ -38,8 +305,16 @@ mod imp {
     #[derive(CompositeTemplate, Default)]
     #[template(file = "src/ui/window.blp")]
     pub struct GliderCloneWindow {
+        #[template_child]
+        pub toast_overlay: TemplateChild<adw::ToastOverlay>,
         #[template_child]
         pub nav_view: TemplateChild<NavigationView>,
+        #[template_child]
+        pub split_view: TemplateChild<NavigationSplitView>,
+        pub offline_progress_toast: RefCell<Option<adw::Toast>>,
+        pub feed_page: OnceCell<FeedPage>,
+        pub story_page: OnceCell<StoryPage>,
+        pub settings: OnceCell<gio::Settings>,
     }
 
     // The central trait for subclassing a GObject
@@ -52,6 +327,13 @@ mod imp {
 
         fn class_init(klass: &mut Self::Class) {
             klass.bind_template();
+
+            klass.install_action("win.preferences", None, |window, _, _| {
+                window.show_preferences(false);
+            });
+            klass.install_action("win.show-mute-filters", None, |window, _, _| {
+                window.show_preferences(true);
+            });
         }
 
         fn instance_init(obj: &InitializingObject<Self>) {
@@ -68,7 +350,8 @@ mod imp {
             self.parent_constructed();
 
             // Setup
-            let _obj = self.obj();
+            let obj = self.obj();
+            obj.setup_actions();
         }
     }
     // ANCHOR_END: constructed